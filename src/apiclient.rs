@@ -1,26 +1,148 @@
-use crate::{types::*, Error, UserCache};
+use crate::{
+    query::QueryDefaults,
+    types::*,
+    util::{json_error, JsonArraySplitter},
+    Error, UserCache,
+};
+use futures_util::stream::Stream;
 use reqwest::{
-    header::{HeaderMap, HeaderValue},
-    Response,
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Request, RequestBuilder, Response,
 };
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
-use std::sync::{Arc, RwLock};
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, RwLock, Weak,
+    },
+    time::Duration,
+};
 
 const DEFAULT_ENDPOINT: &str = "https://zenkit.com/api/v1";
 const API_TOKEN_ENV_VAR: &str = "ZENKIT_API_TOKEN";
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_TRANSFER_TIMEOUT_SECS: u64 = 60 * 60;
+/// Number of activities requested per page by get_list_comments
+const ACTIVITY_PAGE_SIZE: usize = 100;
+// header names must be lowercase for HeaderName::from_static
+const DEFAULT_AUTH_HEADER: &str = "zenkit-api-key";
 
 /// Zenkit http/API client
 #[derive(Debug)]
 pub struct ApiClient {
     client: reqwest::Client,
+    /// auth, user-agent, and content-type headers, added to each request
+    headers: HeaderMap,
     url_prefix: String, // url prefix
-    ratelimit: Option<u32>,
-    ratelimit_remaining: Option<u32>,
+    /// rate limit headers of the most recent response
+    ratelimit: RwLock<RateLimit>,
     /// cache of workspaces, loaded with get_all_workspaces_and_ids
     workspaces: RwLock<Vec<Arc<WorkspaceData>>>,
     /// cache of lists
     lists: RwLock<Vec<Arc<ListInfo>>>,
+    /// default query settings
+    query_defaults: QueryDefaults,
+    /// retries of read requests after network errors
+    network_retries: u32,
+    /// retries after rate limit errors
+    max_retries: u32,
+    retry_backoff: Duration,
+    retry_writes: bool,
+    /// timeout for file transfers and streamed entries
+    /// None if a client was supplied in ApiConfig.client
+    transfer_timeout: Option<Duration>,
+    /// number of retries after rate limit errors
+    retry_count: AtomicU32,
+    /// reference to this client, for ListInfo and Item
+    self_ref: Weak<ApiClient>,
+}
+
+/// Rate limit headers of a response
+#[derive(Debug, Default)]
+struct RateLimit {
+    limit: Option<u32>,
+    remaining: Option<u32>,
+    reset: Option<DateTime<Utc>>,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        use chrono::TimeZone as _;
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        Self {
+            limit: header("x-ratelimit-limit").and_then(|v| v.parse().ok()),
+            remaining: header("x-ratelimit-remaining").and_then(|v| v.parse().ok()),
+            reset: header("x-ratelimit-reset")
+                .and_then(|v| v.parse::<i64>().ok())
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                .map(DateTime::from),
+        }
+    }
+}
+
+/// Returns the delay advised by a Retry-After header, given in seconds or as an http date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    // a date in the past means no delay
+    Some(
+        (date.with_timezone(&Utc) - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns the client referenced by ListInfo or Item,
+/// or an error if the client has been dropped
+pub(crate) fn upgrade(api: &Weak<ApiClient>) -> Result<Arc<ApiClient>, Error> {
+    api.upgrade()
+        .ok_or_else(|| Error::Other("ApiClient has been dropped".to_string()))
+}
+
+/// Returns None if the response is a rate limit error (http 429, or Zenkit error D1 or D2),
+/// otherwise returns the response. If the body was read to check the error code,
+/// the response is reconstructed with the same status, headers, and body.
+async fn unless_rate_limited(resp: Response) -> Result<Option<Response>, Error> {
+    let status = resp.status();
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Ok(None);
+    }
+    let headers = resp.headers().clone();
+    let bytes = resp.bytes().await?;
+    if let Ok(err_res) = serde_json::from_slice::<ErrorResult>(&bytes) {
+        if Error::ApiError(status.as_u16(), Some(err_res.error)).is_rate_limit() {
+            return Ok(None);
+        }
+    }
+    let mut rebuilt = http::Response::new(bytes);
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(Some(Response::from(rebuilt)))
+}
+
+/// Maximum delay between retries, when doubling the backoff
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+
+// Returns the backoff for the next retry: double the current backoff,
+// up to MAX_RETRY_BACKOFF (or the current backoff, if it is larger)
+fn next_backoff(backoff: Duration) -> Duration {
+    backoff
+        .checked_mul(2)
+        .unwrap_or(Duration::MAX)
+        .min(MAX_RETRY_BACKOFF.max(backoff))
+}
+
+// Delays before retrying a request. In wasm, there is no delay.
+async fn sleep(delay: Duration) {
+    #[cfg(not(target_arch = "wasm32"))]
+    tokio::time::sleep(delay).await;
+    #[cfg(target_arch = "wasm32")]
+    let _ = delay;
 }
 
 /// Initialization parameters for Zenkit Api client
@@ -33,6 +155,63 @@ pub struct ApiConfig {
     pub token: String,
     /// HTTPS endpoint. Defaults to "https://zenkit.com/api/v1"
     pub endpoint: String,
+    /// Maximum idle connections per host kept in the connection pool.
+    /// Defaults to None (reqwest default). Ignored in wasm.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// How long idle connections are kept in the pool.
+    /// Defaults to None (reqwest default). Ignored in wasm.
+    pub pool_idle_timeout: Option<Duration>,
+    /// TCP keepalive interval. Defaults to None (no keepalive). Ignored in wasm.
+    pub tcp_keepalive: Option<Duration>,
+    /// Timeout for each request, from sending the request until the response body has been read.
+    /// Defaults to 30 seconds. None for no timeout. Ignored in wasm.
+    /// Large requests, such as get_items with a large page size, may need a longer timeout.
+    /// File transfers and streamed entries use transfer_timeout instead.
+    pub timeout: Option<Duration>,
+    /// Timeout for connecting to the server. Defaults to 10 seconds. None for no timeout.
+    /// Ignored in wasm.
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for requests that may transfer large amounts of data (upload_file,
+    /// download_file, download_file_stream, and get_list_entries_stream), used instead
+    /// of timeout, from sending the request until the response body has been read.
+    /// Defaults to 1 hour. Ignored in wasm, or if client is supplied.
+    pub transfer_timeout: Duration,
+    /// Default settings for ListInfo::get_items and ListInfo::query
+    pub default_query: QueryDefaults,
+    /// Number of times a read request (GET, or a POST of an entry query) is retried
+    /// after a connection error or timeout. Requests are not retried after an http error
+    /// status. Other writes are never retried. Each retry is delayed by retry_backoff,
+    /// doubled for each further retry. Defaults to 0 (no retries).
+    pub network_retries: u32,
+    /// Name of the header used to send the token, for proxies or gateways that
+    /// expect a different header, such as "Authorization". Defaults to "Zenkit-API-Key".
+    pub auth_header_name: Option<String>,
+    /// Optional scheme sent before the token in the auth header, such as "Bearer".
+    /// Defaults to None (the header value is the token).
+    pub auth_scheme: Option<String>,
+    /// Number of times a request is retried after a rate limit error (see Error::is_rate_limit),
+    /// or after a 503 (service unavailable) response with a Retry-After header.
+    /// After a 503, the client waits for the time given by Retry-After, which may be long
+    /// during maintenance. Only read requests are retried, unless retry_writes is true.
+    /// Defaults to 0 (no retries).
+    pub max_retries: u32,
+    /// Delay before the first retry after a rate limit error, doubled for each further retry,
+    /// up to 1 minute (or retry_backoff, if it is longer).
+    /// If the response has a x-ratelimit-reset header in the future, the client waits
+    /// until then instead. Defaults to 1 second. Ignored in wasm (retries are not delayed).
+    pub retry_backoff: Duration,
+    /// Whether writes (POST, PUT, DELETE) are retried after rate limit errors.
+    /// Defaults to false, since retrying a write that was partially applied may
+    /// apply it twice.
+    pub retry_writes: bool,
+    /// Http client to use instead of a client constructed from this config, for example,
+    /// to share a connection pool, or to use custom TLS roots. The auth, User-Agent, and
+    /// Content-Type headers are added to each request, replacing any default headers of
+    /// the client with the same names. When a client is supplied, its own connection pool,
+    /// timeout, and compression settings are used, and pool_max_idle_per_host,
+    /// pool_idle_timeout, tcp_keepalive, timeout, connect_timeout, and transfer_timeout
+    /// are ignored. Defaults to None.
+    pub client: Option<reqwest::Client>,
 }
 
 impl Default for ApiConfig {
@@ -40,6 +219,20 @@ impl Default for ApiConfig {
         Self {
             endpoint: String::from(DEFAULT_ENDPOINT),
             token: std::env::var(API_TOKEN_ENV_VAR).ok().unwrap_or_default(),
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT_SECS)),
+            connect_timeout: Some(Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS)),
+            transfer_timeout: Duration::from_secs(DEFAULT_TRANSFER_TIMEOUT_SECS),
+            default_query: QueryDefaults::default(),
+            network_retries: 0,
+            auth_header_name: None,
+            auth_scheme: None,
+            max_retries: 0,
+            retry_backoff: Duration::from_secs(1),
+            retry_writes: false,
+            client: None,
         }
     }
 }
@@ -56,10 +249,13 @@ fn user_agent_header() -> HeaderValue {
 
 impl ApiClient {
     /// Constructs a new ApiClient.
-    /// Because there are some functions that use the "global" get_api(),
-    /// most users should use [init_api] instead of this constructor.
+    /// Each client has its own connection pool and caches, so an application can use
+    /// several clients, for example, for different Zenkit accounts.
+    /// ListInfo and Item objects use the client that loaded them.
+    /// Applications that use a single client may find it more convenient to use
+    /// [init_api](crate::init_api) and [get_api](crate::get_api).
     /// Error if token is non-ascii
-    pub(crate) fn new(config: ApiConfig) -> Result<Self, Error> {
+    pub fn new(mut config: ApiConfig) -> Result<Arc<Self>, Error> {
         use reqwest::header::{CONTENT_TYPE, USER_AGENT};
         if config.token.is_empty() {
             let env_is_set = match std::env::var(API_TOKEN_ENV_VAR) {
@@ -80,49 +276,231 @@ impl ApiClient {
         let mut headers = HeaderMap::new();
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
         headers.insert(USER_AGENT, user_agent_header());
+        let auth_header = match &config.auth_header_name {
+            Some(name) => HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| Error::Other(format!("Invalid auth header name '{}'", name)))?,
+            None => HeaderName::from_static(DEFAULT_AUTH_HEADER),
+        };
+        let auth_value = match &config.auth_scheme {
+            Some(scheme) => format!("{} {}", scheme, config.token),
+            None => config.token.clone(),
+        };
         headers.insert(
-            "Zenkit-API-Key",
-            HeaderValue::from_str(&config.token)
+            auth_header,
+            HeaderValue::from_str(&auth_value)
                 .map_err(|_| Error::Other("token has non-ascii chars".to_string()))?,
         );
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .build()?;
+        // a supplied client's timeout is used for all requests
+        let transfer_timeout = match config.client {
+            Some(_) => None,
+            None => Some(config.transfer_timeout),
+        };
+        let client = match config.client.take() {
+            Some(client) => client,
+            None => {
+                let builder = reqwest::Client::builder();
+                // Request compressed responses. The client adds the Accept-Encoding header,
+                // and response bodies are decompressed before they are read in json().
+                // In wasm, compression is handled by the browser.
+                #[cfg(not(target_arch = "wasm32"))]
+                let builder = builder.gzip(true).brotli(true);
+                // connection pool and timeout settings
+                #[cfg(not(target_arch = "wasm32"))]
+                let builder = {
+                    let mut builder = builder.tcp_keepalive(config.tcp_keepalive);
+                    if let Some(max_idle) = config.pool_max_idle_per_host {
+                        builder = builder.pool_max_idle_per_host(max_idle);
+                    }
+                    if let Some(timeout) = config.pool_idle_timeout {
+                        builder = builder.pool_idle_timeout(timeout);
+                    }
+                    if let Some(timeout) = config.timeout {
+                        builder = builder.timeout(timeout);
+                    }
+                    if let Some(timeout) = config.connect_timeout {
+                        builder = builder.connect_timeout(timeout);
+                    }
+                    builder
+                };
+                builder.build()?
+            }
+        };
 
-        Ok(Self {
+        Ok(Arc::new_cyclic(|self_ref| Self {
             client,
+            headers,
             url_prefix: config.endpoint,
-            ratelimit: None,
-            ratelimit_remaining: None,
+            ratelimit: RwLock::new(RateLimit::default()),
             workspaces: RwLock::new(Vec::new()),
             lists: RwLock::new(Vec::new()),
-        })
+            query_defaults: config.default_query,
+            network_retries: config.network_retries,
+            max_retries: config.max_retries,
+            retry_backoff: config.retry_backoff,
+            retry_writes: config.retry_writes,
+            transfer_timeout,
+            retry_count: AtomicU32::new(0),
+            self_ref: self_ref.clone(),
+        }))
     }
 
-    /// Returns the rate limit returned on the most recent api call
-    /// Not yet implemented
+    /// Returns the default query settings from ApiConfig
+    pub fn query_defaults(&self) -> &QueryDefaults {
+        &self.query_defaults
+    }
+
+    /// Returns the rate limit (x-ratelimit-limit header) of the most recent api response,
+    /// or None if the header was not present
     pub fn get_rate_limit(&self) -> Option<u32> {
-        self.ratelimit
+        self.ratelimit.read().ok()?.limit
     }
 
-    /// Returns the rate limit remaining on the most recent api call
-    /// Not yet implemented
+    /// Returns the number of requests remaining (x-ratelimit-remaining header)
+    /// of the most recent api response, or None if the header was not present
     pub fn get_rate_limit_remaining(&self) -> Option<u32> {
-        self.ratelimit_remaining
+        self.ratelimit.read().ok()?.remaining
+    }
+
+    /// Returns the time the rate limit resets (x-ratelimit-reset header, in unix seconds)
+    /// of the most recent api response, or None if the header was not present
+    pub fn get_rate_limit_reset(&self) -> Option<DateTime<Utc>> {
+        self.ratelimit.read().ok()?.reset.clone()
+    }
+
+    /// Returns the number of retries made after rate limit errors, or after the service
+    /// was unavailable, since the client was created
+    pub fn rate_limit_retries(&self) -> u32 {
+        self.retry_count.load(Ordering::Relaxed)
+    }
+
+    /// Sends the request. GET and other safe requests are retried after a connection error
+    /// or timeout, up to ApiConfig.network_retries times, and after a rate limit error,
+    /// up to ApiConfig.max_retries times.
+    async fn send(&self, req: RequestBuilder) -> Result<Response, Error> {
+        let req = self.build(req)?;
+        let read_only = req.method().is_safe();
+        self.execute(req, read_only).await
+    }
+
+    /// Sends a POST request that only reads data (an entry query), so it can be retried
+    /// like a GET request.
+    async fn send_query(&self, req: RequestBuilder) -> Result<Response, Error> {
+        self.execute(self.build(req)?, true).await
+    }
+
+    /// Builds the request, adding the auth, User-Agent, and Content-Type headers
+    /// unless the request has set them (for example, Content-Type of file uploads)
+    fn build(&self, req: RequestBuilder) -> Result<Request, Error> {
+        let mut req = req.build()?;
+        for (name, value) in self.headers.iter() {
+            if !req.headers().contains_key(name) {
+                req.headers_mut().insert(name.clone(), value.clone());
+            }
+        }
+        Ok(req)
+    }
+
+    // Sets the timeout of a request that may transfer large amounts of data
+    #[cfg(not(target_arch = "wasm32"))]
+    fn with_transfer_timeout(&self, req: RequestBuilder) -> RequestBuilder {
+        match self.transfer_timeout {
+            Some(timeout) => req.timeout(timeout),
+            None => req,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn with_transfer_timeout(&self, req: RequestBuilder) -> RequestBuilder {
+        req
+    }
+
+    async fn execute(&self, req: Request, read_only: bool) -> Result<Response, Error> {
+        let mut network_retries = if read_only { self.network_retries } else { 0 };
+        let mut rate_limit_retries = if read_only || self.retry_writes {
+            self.max_retries
+        } else {
+            0
+        };
+        let mut backoff = self.retry_backoff;
+        loop {
+            // requests without streaming bodies can always be cloned
+            let attempt = match (network_retries + rate_limit_retries, req.try_clone()) {
+                (0, _) | (_, None) => {
+                    let resp = self.client.execute(req).await?;
+                    *self.ratelimit.write()? = RateLimit::from_headers(resp.headers());
+                    return Ok(resp);
+                }
+                (_, Some(attempt)) => attempt,
+            };
+            let resp = match self.client.execute(attempt).await {
+                Err(e) if network_retries > 0 && (e.is_connect() || e.is_timeout()) => {
+                    network_retries -= 1;
+                    sleep(backoff).await;
+                    backoff = next_backoff(backoff);
+                    continue;
+                }
+                result => result?,
+            };
+            let ratelimit = RateLimit::from_headers(resp.headers());
+            let reset = ratelimit.reset.clone();
+            *self.ratelimit.write()? = ratelimit;
+            if rate_limit_retries == 0 {
+                return Ok(resp);
+            }
+            let delay = if resp.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+                // retry only if the server said when
+                match retry_after(resp.headers()) {
+                    Some(delay) => delay,
+                    None => return Ok(resp),
+                }
+            } else if resp.status().is_client_error() {
+                if let Some(resp) = unless_rate_limited(resp).await? {
+                    return Ok(resp);
+                }
+                reset
+                    .and_then(|t| (*t - Utc::now()).to_std().ok())
+                    .unwrap_or(backoff)
+            } else {
+                return Ok(resp);
+            };
+            rate_limit_retries -= 1;
+            self.retry_count.fetch_add(1, Ordering::Relaxed);
+            sleep(delay).await;
+            backoff = next_backoff(backoff);
+        }
     }
 
     /// Check response for http errors and deserialize to requested object type.
     /// This is called on every response returned from the api client
     async fn json<T: DeserializeOwned>(&self, resp: Response) -> Result<T, Error> {
-        //
-        // see if api gave us pushback for rate limit
-        //   if so, store them internally; otherwise, set None for those fields
-        // Since self is not mut, we would need to store these in a Cell
-        // match resp.headers.get("x-ratelimit-limit")
-        // match resp.headers.get("x-ratelimit-remaining")
-        // match resp.headers.get("x-ratelimit-reset") // time when api ok to use again
-        //
+        let bytes = self.check_status(resp).await?;
+        match serde_json::from_slice(&bytes) {
+            Ok(obj) => Ok(obj),
+            Err(e) => {
+                eprintln!(
+                    "Error deserializing result {}. data:\n{}",
+                    e.to_string(),
+                    String::from_utf8_lossy(&bytes)
+                );
+                Err(json_error::<T>(e))
+            }
+        }
+    }
+
+    /// Check response for http errors, and ignore the response body.
+    /// Used by methods that return no data, since the body may be empty (204 No Content).
+    async fn expect_no_content(&self, resp: Response) -> Result<(), Error> {
+        self.check_status(resp).await.map(|_| ())
+    }
+
+    /// Returns the response body, or an error if the response status is not success
+    async fn check_status(&self, resp: Response) -> Result<bytes::Bytes, Error> {
         let status = &resp.status();
+        if *status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(Error::ServiceUnavailable {
+                retry_after: retry_after(resp.headers()),
+            });
+        }
         let bytes = resp.bytes().await?;
         if !status.is_success() {
             // attempt to parse response as Zenkit error
@@ -140,17 +518,7 @@ impl ApiClient {
                 String::from_utf8_lossy(bytes.as_ref())
             )));
         }
-        match serde_json::from_slice(&bytes) {
-            Ok(obj) => Ok(obj),
-            Err(e) => {
-                eprintln!(
-                    "Error deserializing result {}. data:\n{}",
-                    e.to_string(),
-                    String::from_utf8_lossy(&bytes)
-                );
-                Err(e.into())
-            }
-        }
+        Ok(bytes)
     }
 
     /// Returns users in workspace. This method caches the user list so subsequent
@@ -173,14 +541,14 @@ impl ApiClient {
         // drop to avoid holding lock across await. Cost is a very small chance of duplicate
         // fetches but gain is preventing delay of other uses of workspace list.
         drop(ws_cache_read);
-        Ok(wd2.users().await?)
+        wd2.users(self).await
     }
 
     /// Returns users in the workspace. Bypasses cache and uses zenkit api directly.
     /// See also get_users.
     pub async fn get_users_raw(&self, workspace_id: ID) -> Result<Vec<User>, Error> {
         let url = format!("{}/workspaces/{}/users", self.url_prefix, workspace_id);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.send(self.client.get(&url)).await?;
         self.json(resp).await
     }
 
@@ -208,7 +576,7 @@ impl ApiClient {
         };
         let wd2 = wd.clone();
         drop(ws_cache_read);
-        Ok(wd2.find_user(predicate).await?)
+        wd2.find_user(self, predicate).await
     }
 
     /// Finds the user id for the name. Name parameter can be display name, full name, or uuid.
@@ -230,9 +598,68 @@ impl ApiClient {
     /// get accesses for the user
     pub async fn get_user_accesses(&self) -> Result<Vec<Access>, Error> {
         let resp = self
-            .client
-            .get(&format!("{}/users/me/access", self.url_prefix))
-            .send()
+            .send(
+                self.client
+                    .get(format!("{}/users/me/access", self.url_prefix)),
+            )
+            .await?;
+        self.json(resp).await
+    }
+
+    /// Returns true if the user (api token) can modify entries in the list,
+    /// false for read-only and comment-only roles, or None if the user's role
+    /// could not be determined (see get_list_role).
+    pub async fn can_write(&self, list_id: ID) -> Result<Option<bool>, Error> {
+        Ok(self
+            .get_list_role(list_id)
+            .await?
+            .map(|role| role.can_write()))
+    }
+
+    /// Returns true if the user (api token) can add comments to the list and its entries,
+    /// false for read-only roles, or None if the user's role could not be determined
+    /// (see get_list_role).
+    pub async fn can_comment(&self, list_id: ID) -> Result<Option<bool>, Error> {
+        Ok(self
+            .get_list_role(list_id)
+            .await?
+            .map(|role| role.can_comment()))
+    }
+
+    /// Returns the user's (api token's) role for the list, or None if it could not be determined.
+    /// The user's list access takes precedence over workspace access. If the user has
+    /// neither, and has access to exactly one organization, the organization role is returned,
+    /// since workspaces do not identify their organization.
+    pub async fn get_list_role(&self, list_id: ID) -> Result<Option<RoleID>, Error> {
+        let accesses = self.get_user_accesses().await?;
+        if let Some(access) = accesses.iter().find(|a| a.list_id == Some(list_id)) {
+            return Ok(Some(access.role_id));
+        }
+        let workspace = self.get_list_workspace(list_id).await?;
+        if let Some(access) = accesses
+            .iter()
+            .find(|a| a.list_id.is_none() && a.workspace_id == Some(workspace.id))
+        {
+            return Ok(Some(access.role_id));
+        }
+        let mut org_accesses = accesses
+            .iter()
+            .filter(|a| matches!(a.access_type, AccessType::Organization));
+        match (org_accesses.next(), org_accesses.next()) {
+            (Some(access), None) => Ok(Some(access.role_id)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Searches all lists the user can access. Returns up to limit matches.
+    pub async fn global_search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, Error> {
+        let url = format!("{}/users/me/search", self.url_prefix);
+        let resp = self
+            .send(
+                self.client
+                    .get(&url)
+                    .query(&[("query", query), ("limit", &limit.to_string())]),
+            )
             .await?;
         self.json(resp).await
     }
@@ -247,7 +674,21 @@ impl ApiClient {
             self.url_prefix,
             user_allid.into()
         );
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.send(self.client.get(&url)).await?;
+        self.json(resp).await
+    }
+
+    /// Returns background theme and style, for the background_id of a list or workspace
+    pub async fn get_background<A: Into<AllId>>(
+        &self,
+        background_allid: A,
+    ) -> Result<Background, Error> {
+        let url = format!(
+            "{}/backgrounds/{}",
+            self.url_prefix,
+            background_allid.into()
+        );
+        let resp = self.send(self.client.get(&url)).await?;
         self.json(resp).await
     }
 
@@ -257,24 +698,47 @@ impl ApiClient {
         list_allid: A,
     ) -> Result<Vec<Element>, Error> {
         let url = format!("{}/lists/{}/elements", self.url_prefix, list_allid.into());
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.send(self.client.get(&url)).await?;
         self.json(resp).await
     }
 
-    /// Returns a single list item
+    /// Returns a single list item.
+    /// The response may be the entry or an array containing the entry.
     pub async fn get_entry<L: Into<AllId>, E: Into<AllId>>(
         &self,
         list_allid: L,
         entry_allid: E,
+    ) -> Result<Entry, Error> {
+        self.fetch_entry(list_allid.into(), entry_allid.into(), false)
+            .await
+    }
+
+    /// Returns a single list item, even if it has been deprecated (deleted).
+    pub async fn get_entry_allow_deprecated<L: Into<AllId>, E: Into<AllId>>(
+        &self,
+        list_allid: L,
+        entry_allid: E,
+    ) -> Result<Entry, Error> {
+        self.fetch_entry(list_allid.into(), entry_allid.into(), true)
+            .await
+    }
+
+    async fn fetch_entry(
+        &self,
+        list_allid: AllId,
+        entry_allid: AllId,
+        allow_deprecated: bool,
     ) -> Result<Entry, Error> {
         let url = format!(
             "{}/lists/{}/entries/{}",
-            self.url_prefix,
-            list_allid.into(),
-            entry_allid.into()
+            self.url_prefix, list_allid, entry_allid
         );
-        let resp = self.client.get(&url).send().await?;
-        self.json(resp).await
+        let mut req = self.client.get(&url);
+        if allow_deprecated {
+            req = req.query(&[("allowDeprecated", true)]);
+        }
+        let resp = self.send(req).await?;
+        crate::util::from_one_or_array(self.json(resp).await?)
     }
 
     /// Returns items from list (possibly filtered/sorted), with pagination
@@ -290,10 +754,69 @@ impl ApiClient {
             self.url_prefix,
             list_allid.into()
         );
-        let resp = self.client.post(&url).json(&params).send().await?;
+        let resp = self
+            .send_query(self.client.post(&url).json(&params))
+            .await?;
         self.json(resp).await
     }
 
+    /// Returns list items created between 'from' and 'to', with pagination.
+    /// Both boundaries are inclusive: an item created exactly at 'from' or 'to' is returned.
+    /// For a calendar month, use the first instant of the month and the last second of the month.
+    pub async fn get_entries_created_between<A: Into<AllId>>(
+        &self,
+        list_allid: A,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: usize, // number to return per call (or 0 for no limit)
+        skip: usize,  // number to skip
+    ) -> Result<Vec<Entry>, Error> {
+        let list_allid = list_allid.into().to_string();
+        let created = self
+            .get_list_elements(&list_allid)
+            .await?
+            .into_iter()
+            .find(|e| e.element_category == ElementCategoryId::DateCreated)
+            .ok_or_else(|| Error::Other("list has no created-date field".to_string()))?;
+        let q = GetEntriesRequest {
+            filter: crate::filter::FilterBuilder::new()
+                .date_range(created.id, from, to)
+                .build(),
+            limit,
+            skip,
+            ..Default::default()
+        };
+        self.get_list_entries(&list_allid, &q).await
+    }
+
+    /// Returns items from list (possibly filtered/sorted), as a stream that deserializes
+    /// entries one at a time as the response is received, so that only one entry
+    /// at a time is held in memory. Parameters are the same as get_list_entries.
+    pub async fn get_list_entries_stream<A: Into<AllId>>(
+        &self,
+        list_allid: A,
+        params: &GetEntriesRequest,
+    ) -> Result<EntryStream, Error> {
+        let url = format!(
+            "{}/lists/{}/entries/filter",
+            self.url_prefix,
+            list_allid.into()
+        );
+        let resp = self
+            .send_query(self.with_transfer_timeout(self.client.post(&url).json(&params)))
+            .await?;
+        if !resp.status().is_success() {
+            return match self.check_status(resp).await {
+                Err(e) => Err(e),
+                Ok(_) => Err(Error::Other("Unexpected response status".to_string())),
+            };
+        }
+        Ok(EntryStream {
+            resp,
+            splitter: JsonArraySplitter::default(),
+        })
+    }
+
     /// Returns list items sorted by last update (asc or desc), with pagination
     /// Set 'sort' to Some(column-name, direction), e.g., Some("updated_at", Desc)
     pub async fn get_list_entries_sorted<A: Into<AllId>>(
@@ -329,10 +852,140 @@ impl ApiClient {
         params: &GetEntriesViewRequest,
     ) -> Result<GetEntriesViewResponse, Error> {
         let url = format!("{}/lists/{}/entries/filter/list", self.url_prefix, list_id);
-        let resp = self.client.post(&url).json(params).send().await?;
+        let resp = self.send_query(self.client.post(&url).json(params)).await?;
+        self.json(resp).await
+    }
+
+    /// Returns the number of entries in the list matching filter, without downloading
+    /// the entries (the request fetches at most one entry). Deprecated entries are not counted.
+    /// Use an empty object (`json!({})`) to count all entries.
+    pub async fn count_entries(&self, list_id: ID, filter: Value) -> Result<u64, Error> {
+        Ok(self.get_entry_counts(list_id, filter).await?.filtered_total)
+    }
+
+    /// Returns entry counts for the list: count_data.total is the number of entries
+    /// in the list, and count_data.filtered_total is the number matching filter.
+    /// Like count_entries, this fetches at most one entry.
+    pub async fn get_entry_counts(
+        &self,
+        list_id: ID,
+        filter: Value,
+    ) -> Result<FilterCountData, Error> {
+        let params = GetEntriesViewRequest {
+            filter,
+            limit: 1,
+            ..Default::default()
+        };
+        Ok(self
+            .get_list_entries_for_view(list_id, &params)
+            .await?
+            .count_data)
+    }
+
+    /// Uploads a file, and attaches it to the files field element_id of entry entry_id.
+    /// The file is sent as multipart/form-data, with the given file name and mime type
+    /// (for example, "application/pdf").
+    /// Returns the uploaded file. Some properties, such as size and is_image,
+    /// may be None until Zenkit has processed the file.
+    pub async fn upload_file(
+        &self,
+        list_id: ID,
+        element_id: ID,
+        entry_id: ID,
+        filename: &str,
+        bytes: Vec<u8>,
+        mime: &str,
+    ) -> Result<File, Error> {
+        use reqwest::multipart::{Form, Part};
+        let url = format!("{}/lists/{}/files", self.url_prefix, list_id);
+        let part = Part::bytes(bytes)
+            .file_name(filename.to_string())
+            .mime_str(mime)?;
+        let form = Form::new()
+            .text("elementId", element_id.to_string())
+            .text("listEntryId", entry_id.to_string())
+            .part("file", part);
+        let resp = self
+            .send(self.with_transfer_timeout(self.client.post(&url).multipart(form)))
+            .await?;
         self.json(resp).await
     }
 
+    /// Downloads a file, returning its contents.
+    /// Returns Error::MissingFileUrl if the file has no url.
+    /// For large files, see download_file_stream.
+    pub async fn download_file(&self, file: &File) -> Result<Vec<u8>, Error> {
+        let resp = self.get_file(file).await?;
+        Ok(self.check_status(resp).await?.to_vec())
+    }
+
+    /// Downloads a file, returning a stream of chunks of its contents,
+    /// so that large files don't need to be held in memory.
+    /// Returns Error::MissingFileUrl if the file has no url, or an error if the
+    /// response status is not success. Errors while reading are returned by the stream.
+    pub async fn download_file_stream(
+        &self,
+        file: &File,
+    ) -> Result<impl Stream<Item = Result<bytes::Bytes, Error>>, Error> {
+        use futures_util::stream;
+
+        let resp = self.get_file(file).await?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(match self.check_status(resp).await {
+                Err(e) => e,
+                Ok(_) => Error::Other(format!("Server returned status {}", status.as_u16())),
+            });
+        }
+        // state is the response, or None after an error
+        Ok(stream::unfold(Some(resp), |resp| async move {
+            let mut resp = resp?;
+            match resp.chunk().await {
+                Ok(Some(chunk)) => Some((Ok(chunk), Some(resp))),
+                Ok(None) => None,
+                Err(e) => Some((Err(Error::from(e)), None)),
+            }
+        }))
+    }
+
+    // Sends request for file contents
+    async fn get_file(&self, file: &File) -> Result<Response, Error> {
+        let req = self.file_request(file)?;
+        self.execute(req, true).await
+    }
+
+    // Builds request for file contents. The api token is sent only if the file url
+    // has the same scheme, host, and port as the api endpoint, so that it is not
+    // disclosed to linked files or other file providers.
+    fn file_request(&self, file: &File) -> Result<Request, Error> {
+        let url = file
+            .file_url
+            .as_deref()
+            .ok_or(Error::MissingFileUrl(file.id))?;
+        let req = self.with_transfer_timeout(self.client.get(url));
+        if self.is_api_url(url) {
+            self.build(req)
+        } else {
+            Ok(req.build()?)
+        }
+    }
+
+    // Returns true if the url is on the same server (scheme, host, and port) as the api endpoint
+    fn is_api_url(&self, url: &str) -> bool {
+        match (
+            reqwest::Url::parse(url),
+            reqwest::Url::parse(&self.url_prefix),
+        ) {
+            (Ok(url), Ok(api)) => {
+                url.scheme() == api.scheme()
+                    && url.host_str().is_some()
+                    && url.host_str() == api.host_str()
+                    && url.port_or_known_default() == api.port_or_known_default()
+            }
+            _ => false,
+        }
+    }
+
     /// Update checklists
     pub async fn update_checklists<L: Into<AllId>, E: Into<AllId>>(
         &self,
@@ -347,8 +1000,63 @@ impl ApiClient {
             entry_allid.into()
         );
         let data = UpdateChecklistParam { checklists };
-        let resp = self.client.put(&url).json(&data).send().await?;
-        self.json(resp).await
+        let resp = self.send(self.client.put(&url).json(&data)).await?;
+        self.expect_no_content(resp).await
+    }
+
+    /// Updates one checklist of an entry, matched by uuid, or adds the checklist if the entry
+    /// has no checklist with the same uuid (or checklist.uuid is None).
+    /// Zenkit only supports replacing the full set of checklists, so this reads
+    /// the entry's checklists first, then sends the full set with the one checklist changed.
+    /// No update is sent if the checklist is unchanged.
+    pub async fn update_checklist(
+        &self,
+        list_id: ID,
+        entry_id: ID,
+        checklist: &Checklist,
+    ) -> Result<(), Error> {
+        let mut checklists = self.get_entry(list_id, entry_id).await?.checklists;
+        match checklists
+            .iter_mut()
+            .find(|c| c.uuid.is_some() && c.uuid == checklist.uuid)
+        {
+            Some(existing) if existing == checklist => return Ok(()),
+            Some(existing) => *existing = checklist.clone(),
+            None => checklists.push(checklist.clone()),
+        }
+        self.update_checklists(list_id, entry_id, checklists).await
+    }
+
+    /// Subscribes the current user to an entry, to receive notifications of changes
+    pub async fn subscribe_entry<L: Into<AllId>, E: Into<AllId>>(
+        &self,
+        list_allid: L,
+        entry_allid: E,
+    ) -> Result<(), Error> {
+        let url = format!(
+            "{}/lists/{}/entries/{}/subscription",
+            self.url_prefix,
+            list_allid.into(),
+            entry_allid.into()
+        );
+        let resp = self.send(self.client.post(&url)).await?;
+        self.expect_no_content(resp).await
+    }
+
+    /// Unsubscribes the current user from notifications for an entry
+    pub async fn unsubscribe_entry<L: Into<AllId>, E: Into<AllId>>(
+        &self,
+        list_allid: L,
+        entry_allid: E,
+    ) -> Result<(), Error> {
+        let url = format!(
+            "{}/lists/{}/entries/{}/subscription",
+            self.url_prefix,
+            list_allid.into(),
+            entry_allid.into()
+        );
+        let resp = self.send(self.client.delete(&url)).await?;
+        self.expect_no_content(resp).await
     }
 
     /// Delete a list entry
@@ -363,10 +1071,31 @@ impl ApiClient {
             list_allid.into(),
             entry_allid.into()
         );
-        let resp = self.client.delete(&url).send().await?;
+        let resp = self.send(self.client.delete(&url)).await?;
         self.json(resp).await
     }
 
+    /// Delete multiple list entries with a single bulk request.
+    /// Returns the number of entries removed.
+    pub async fn delete_entries<L: Into<AllId>>(
+        &self,
+        list_allid: L,
+        entry_ids: &[ID],
+    ) -> Result<u64, Error> {
+        let url = format!(
+            "{}/lists/{}/entries/delete",
+            self.url_prefix,
+            list_allid.into()
+        );
+        let data = DeleteEntriesParam {
+            list_entry_ids: entry_ids,
+            should_delete_all: false,
+        };
+        let resp = self.send(self.client.post(&url).json(&data)).await?;
+        let result: DeleteListEntriesResponse = self.json(resp).await?;
+        Ok(result.count)
+    }
+
     // Returns true if workspaces have been loaded
     fn have_workspaces(&self) -> Result<bool, Error> {
         let ws_cache = self.workspaces.read()?;
@@ -403,23 +1132,45 @@ impl ApiClient {
         Ok(wd)
     }
 
+    /// Returns all workspaces that the current user can access, without their lists
+    /// (Workspace.lists is empty). This is less expensive than get_all_workspaces_and_lists.
+    /// Results are not cached, and do not affect the cache used by get_workspace.
+    /// To cache workspaces without their lists, use get_all_workspaces.
+    pub async fn get_workspaces(&self) -> Result<Vec<Workspace>, Error> {
+        let resp = self
+            .send(
+                self.client
+                    .get(format!("{}/users/me/workspaces", self.url_prefix)),
+            )
+            .await?;
+        self.json(resp).await
+    }
+
     /// Loads all workspaces and lists that the current user can access.
     ///
     /// Performance notes:
     /// - Repeaded calls return cached data.
+    /// - If workspaces were cached without their lists (see get_all_workspaces),
+    ///   all workspaces are reloaded with their lists.
     pub async fn get_all_workspaces_and_lists(&self) -> Result<Vec<Arc<Workspace>>, Error> {
-        if !self.have_workspaces()? {
+        let have_lists = {
+            let ws_cache = self.workspaces.read()?;
+            !ws_cache.is_empty() && ws_cache.iter().all(|wd| wd.with_lists)
+        };
+        if !have_lists {
             let resp = self
-                .client
-                .get(&format!("{}/users/me/workspacesWithLists", self.url_prefix))
-                .send()
+                .send(
+                    self.client
+                        .get(format!("{}/users/me/workspacesWithLists", self.url_prefix)),
+                )
                 .await?;
             let ws_list: Vec<Workspace> = self.json(resp).await?;
             let mut ws_cache_write = self.workspaces.write()?;
+            ws_cache_write.clear();
             ws_cache_write.append(
                 &mut ws_list
                     .into_iter()
-                    .map(|w| Arc::new(WorkspaceData::new(w)))
+                    .map(|w| Arc::new(WorkspaceData::new(w, true)))
                     .collect(),
             );
             // drop write lock
@@ -428,6 +1179,51 @@ impl ApiClient {
         Ok(ws_cache.iter().map(|wd| wd.workspace.clone()).collect())
     }
 
+    /// Loads all workspaces that the current user can access, without their lists.
+    /// For accounts with many large workspaces, this is less expensive than
+    /// get_all_workspaces_and_lists. Lists of a workspace are loaded on first use,
+    /// by workspace_lists or get_list_info, and cached.
+    ///
+    /// Performance notes:
+    /// - Repeated calls return cached data.
+    /// - Workspace.lists of the returned workspaces is empty unless the workspace
+    ///   was previously loaded with its lists. Use workspace_lists to get the lists.
+    pub async fn get_all_workspaces(&self) -> Result<Vec<Arc<Workspace>>, Error> {
+        if !self.have_workspaces()? {
+            let ws_list = self.get_workspaces().await?;
+            let mut ws_cache_write = self.workspaces.write()?;
+            ws_cache_write.append(
+                &mut ws_list
+                    .into_iter()
+                    .map(|w| Arc::new(WorkspaceData::new(w, false)))
+                    .collect(),
+            );
+        }
+        let ws_cache = self.workspaces.read()?;
+        Ok(ws_cache.iter().map(|wd| wd.workspace.clone()).collect())
+    }
+
+    /// Returns the lists in a workspace. `ws_id` may be ID, UUID, or title.
+    /// If the workspace was loaded without its lists (see get_all_workspaces),
+    /// the lists are fetched on first use and cached.
+    pub async fn workspace_lists(&self, ws_id: &str) -> Result<Vec<List>, Error> {
+        let wd = match self.get_cached_workspace_allid(ws_id) {
+            Ok(wd) => wd,
+            Err(_) => {
+                let id = self.get_workspace(ws_id).await?.id;
+                self.get_cached_workspace(id)?
+            }
+        };
+        wd.lists(self).await
+    }
+
+    // Fetches the lists in a workspace
+    async fn get_workspace_lists_raw(&self, ws_id: ID) -> Result<Vec<List>, Error> {
+        let url = format!("{}/workspaces/{}/lists", self.url_prefix, ws_id);
+        let resp = self.send(self.client.get(&url)).await?;
+        self.json(resp).await
+    }
+
     /// Returns the workspace. `ws_id` may be ID, UUID, or title.
     ///
     /// Performance notes:
@@ -452,13 +1248,7 @@ impl ApiClient {
         // if ws_id is an int or uuid, we can get just one using the call below.
         // If it's a workspace title, we need to get all since api doesn't support get-by-name.
         if ws_id.parse::<i64>().is_ok() || crate::util::is_uuid(ws_id) {
-            let url = format!("{}/workspaces/{}", self.url_prefix, ws_id);
-            let resp = self.client.get(&url).send().await?;
-            let ws_data = WorkspaceData::new(self.json(resp).await?);
-            let mut cache_write = self.workspaces.write()?;
-            let ws_copy = ws_data.workspace.clone();
-            cache_write.push(Arc::new(ws_data));
-            return Ok(ws_copy);
+            return self.fetch_workspace(ws_id).await;
         }
 
         // load & cache all workspaces
@@ -470,6 +1260,158 @@ impl ApiClient {
         Err(Error::Other(format!("Workspace '{}' not found", ws_id)))
     }
 
+    /// Returns the workspace, bypassing the cache. `ws_id` may be ID, UUID, or title.
+    /// The workspace is fetched from Zenkit and replaces any cached copy.
+    /// If `ws_id` is a title, the cache is used to find the workspace id.
+    pub async fn get_workspace_fresh(&self, ws_id: &str) -> Result<Arc<Workspace>, Error> {
+        if ws_id.parse::<i64>().is_ok() || crate::util::is_uuid(ws_id) {
+            return self.fetch_workspace(ws_id).await;
+        }
+        let id = self.get_workspace(ws_id).await?.id;
+        self.fetch_workspace(&id.to_string()).await
+    }
+
+    // Fetches workspace by id or uuid, and adds it to the cache,
+    // replacing any previously cached workspace with the same id.
+    async fn fetch_workspace(&self, ws_id: &str) -> Result<Arc<Workspace>, Error> {
+        let url = format!("{}/workspaces/{}", self.url_prefix, ws_id);
+        let resp = self.send(self.client.get(&url)).await?;
+        let ws: Workspace = self.json(resp).await?;
+        // if the response had no lists, they are loaded on first use
+        let with_lists = !ws.lists.is_empty();
+        let ws_data = WorkspaceData::new(ws, with_lists);
+        let ws_copy = ws_data.workspace.clone();
+        let mut cache_write = self.workspaces.write()?;
+        cache_write.retain(|wd| wd.workspace.id != ws_copy.id);
+        cache_write.push(Arc::new(ws_data));
+        Ok(ws_copy)
+    }
+
+    /// Creates a workspace. Returns the new workspace, which is added to the cache.
+    pub async fn create_workspace(
+        &self,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<Workspace, Error> {
+        let url = format!("{}/workspaces", self.url_prefix);
+        let data = NewWorkspaceParam { name, description };
+        let resp = self.send(self.client.post(&url).json(&data)).await?;
+        let ws: Workspace = self.json(resp).await?;
+        // a new workspace has no lists
+        let ws_data = WorkspaceData::new(ws.clone(), true);
+        let mut cache_write = self.workspaces.write()?;
+        cache_write.retain(|wd| wd.workspace.id != ws.id);
+        cache_write.push(Arc::new(ws_data));
+        Ok(ws)
+    }
+
+    /// Deletes a workspace, including its lists.
+    /// The workspace, and cached ListInfo of its lists, are removed from the cache.
+    pub async fn delete_workspace(&self, workspace_id: ID) -> Result<(), Error> {
+        let url = format!("{}/workspaces/{}", self.url_prefix, workspace_id);
+        let resp = self.send(self.client.delete(&url)).await?;
+        self.expect_no_content(resp).await?;
+        self.workspaces
+            .write()?
+            .retain(|wd| wd.workspace.id != workspace_id);
+        self.lists
+            .write()?
+            .retain(|li| li.workspace_id != workspace_id);
+        Ok(())
+    }
+
+    /// Returns the workspace containing the list. `list_allid` may be ID, UUID, shortId, or name.
+    ///
+    /// Performance notes:
+    /// - If the list's ListInfo has been loaded, its workspace id is used to
+    ///   look up the workspace (see get_workspace).
+    /// - Otherwise, the list is found in the lists of cached workspaces, loading
+    ///   all workspaces and lists first if none have been loaded.
+    pub async fn get_list_workspace<A: Into<AllId>>(
+        &self,
+        list_allid: A,
+    ) -> Result<Arc<Workspace>, Error> {
+        let list_id = list_allid.into().to_string();
+        if let Ok(li) = self.get_cached_list(&list_id) {
+            return self.get_workspace(&li.workspace_id.to_string()).await;
+        }
+        for w in self.get_all_workspaces_and_lists().await?.iter() {
+            if w.lists.iter().any(|l| l.has_id(&list_id)) {
+                return Ok(w.clone());
+            }
+        }
+        Err(Error::Other(format!(
+            "get_list_workspace: list '{}' not found",
+            list_id
+        )))
+    }
+
+    /// Copies a list, including its field definitions, and optionally its entries.
+    /// The copy is created in the same workspace as the original.
+    /// Returns the new list. The cached workspace is refreshed to include the new list.
+    pub async fn copy_list<A: Into<AllId>>(
+        &self,
+        list_allid: A,
+        new_name: &str,
+        include_entries: bool,
+    ) -> Result<List, Error> {
+        let url = format!("{}/lists/{}/copy", self.url_prefix, list_allid.into());
+        let data = CopyListParam {
+            name: new_name,
+            should_copy_entries: include_entries,
+        };
+        let resp = self.send(self.client.post(&url).json(&data)).await?;
+        let list: List = self.json(resp).await?;
+        self.fetch_workspace(&list.workspace_id.to_string()).await?;
+        Ok(list)
+    }
+
+    /// Updates list properties (name, description, and item names).
+    /// Returns the updated list. The cached workspace is refreshed,
+    /// and the list's cached ListInfo is removed, so it is reloaded on next use.
+    pub async fn update_list(&self, list_id: ID, update: &ListUpdate) -> Result<List, Error> {
+        let url = format!("{}/lists/{}", self.url_prefix, list_id);
+        let resp = self.send(self.client.put(&url).json(update)).await?;
+        let list: List = self.json(resp).await?;
+        self.lists.write()?.retain(|li| li.get_id() != list.id);
+        self.fetch_workspace(&list.workspace_id.to_string()).await?;
+        Ok(list)
+    }
+
+    /// Changes the name of a list. See update_list.
+    pub async fn rename_list(&self, list_id: ID, new_name: &str) -> Result<List, Error> {
+        let update = ListUpdate {
+            name: Some(new_name.to_string()),
+            ..Default::default()
+        };
+        self.update_list(list_id, &update).await
+    }
+
+    /// Creates an empty list in the workspace.
+    /// Returns the new list. The cached workspace is refreshed to include the new list.
+    pub async fn create_list(&self, workspace_id: ID, name: &str) -> Result<List, Error> {
+        let url = format!("{}/workspaces/{}/lists", self.url_prefix, workspace_id);
+        let data = ListPrototype {
+            name: name.to_string(),
+        };
+        let resp = self.send(self.client.post(&url).json(&data)).await?;
+        let list: List = self.json(resp).await?;
+        self.fetch_workspace(&list.workspace_id.to_string()).await?;
+        Ok(list)
+    }
+
+    /// Deletes a list and its entries.
+    /// The list's cached ListInfo is removed, and its cached workspace is refreshed.
+    pub async fn delete_list(&self, list_id: ID) -> Result<(), Error> {
+        let workspace_id = self.get_list_workspace(list_id).await?.id;
+        let url = format!("{}/lists/{}", self.url_prefix, list_id);
+        let resp = self.send(self.client.delete(&url)).await?;
+        self.expect_no_content(resp).await?;
+        self.lists.write()?.retain(|li| li.get_id() != list_id);
+        self.fetch_workspace(&workspace_id.to_string()).await?;
+        Ok(())
+    }
+
     /// Retrieves a list, with field definitions.
     /// list_name parameter can be string name, id, or uuid
     pub async fn get_list_info(
@@ -485,27 +1427,63 @@ impl ApiClient {
         // list_info not cached
         // first get its containing workspace, then load fields
         let wd = match self.get_cached_workspace(workspace_id) {
-            Ok(wd) => wd.workspace.clone(),
+            Ok(wd) => wd,
             Err(_) => {
                 // wasn't cached, try to load it, or fail if invalid id
-                self.get_workspace(&workspace_id.to_string()).await?
+                self.get_workspace(&workspace_id.to_string()).await?;
+                self.get_cached_workspace(workspace_id)?
             }
         };
 
-        let list = match wd.lists.iter().find(|l| l.has_id(list_allid)) {
-            Some(list) => list.clone(),
+        self.fetch_list_info(&wd, list_allid).await
+    }
+
+    /// Retrieves a list, with field definitions, bypassing the cache.
+    /// The workspace and list fields are fetched from Zenkit, and replace any cached copies.
+    /// list_name parameter can be string name, id, or uuid
+    pub async fn get_list_info_fresh(
+        &self,
+        workspace_id: ID,
+        list_allid: &'_ str,
+    ) -> Result<Arc<ListInfo>, Error> {
+        self.get_workspace_fresh(&workspace_id.to_string()).await?;
+        let wd = self.get_cached_workspace(workspace_id)?;
+        self.fetch_list_info(&wd, list_allid).await
+    }
+
+    /// Returns true if the list's fields have changed since the list was cached,
+    /// comparing field uuid, category, and whether multiple values are allowed.
+    /// The list's current fields are fetched, but the cache is not updated;
+    /// use get_list_info_fresh to reload the list.
+    /// Returns an error if the list is not in the cache.
+    pub async fn list_schema_changed(&self, list_allid: &str) -> Result<bool, Error> {
+        let cached = self.get_cached_list(list_allid)?;
+        let fields = self.get_list_elements(cached.get_id()).await?;
+        Ok(cached.schema_differs(&fields))
+    }
+
+    // Loads list fields, and adds the ListInfo to the cache,
+    // replacing any previously cached ListInfo for the same list.
+    async fn fetch_list_info(
+        &self,
+        wd: &WorkspaceData,
+        list_allid: &'_ str,
+    ) -> Result<Arc<ListInfo>, Error> {
+        let list = match wd.find_list(self, list_allid).await? {
+            Some(list) => list,
             None => {
                 return Err(Error::Other(format!(
                     "get_list_info: invalid list '{}' in workspace '{}' ({})",
-                    list_allid, wd.name, workspace_id
+                    list_allid, wd.workspace.name, wd.workspace.id
                 )))
             }
         };
         // load fields
         let fields = self.get_list_elements(list.id).await?;
 
-        let info = Arc::new(ListInfo::new(list, fields));
+        let info = Arc::new(ListInfo::new(list, fields, self.self_ref.clone()));
         let mut list_cache_write = self.lists.write()?;
+        list_cache_write.retain(|li| li.get_id() != info.get_id());
         list_cache_write.push(info.clone());
         Ok(info)
     }
@@ -527,21 +1505,24 @@ impl ApiClient {
     /// Creates a new list entry
     pub async fn create_entry(&self, list_id: ID, val: Value) -> Result<Entry, Error> {
         let url = format!("{}/lists/{}/entries", self.url_prefix, list_id);
-        let resp = self.client.post(&url).json(&val).send().await?;
+        let resp = self.send(self.client.post(&url).json(&val)).await?;
         self.json(resp).await
     }
 
-    /// Creates a new webhook
+    /// Creates a new webhook.
+    /// Returns an error, without calling Zenkit, if the webhook scope is inconsistent
+    /// (see NewWebhook::validate)
     pub async fn create_webhook(&self, webhook: &NewWebhook) -> Result<Webhook, Error> {
+        webhook.validate()?;
         let url = format!("{}/webhooks", self.url_prefix);
-        let resp = self.client.post(&url).json(&webhook).send().await?;
+        let resp = self.send(self.client.post(&url).json(&webhook)).await?;
         self.json(resp).await
     }
 
     /// Deletes webhook
     pub async fn delete_webhook(&self, webhook_id: ID) -> Result<Webhook, Error> {
         let url = format!("{}/webhooks/{}", self.url_prefix, webhook_id);
-        let resp = self.client.delete(&url).send().await?;
+        let resp = self.send(self.client.delete(&url)).await?;
         self.json(resp).await
     }
 
@@ -550,7 +1531,7 @@ impl ApiClient {
         // found this undocumented api by trial-and-error.
         // .. tried /webooks and /workspaces/ID/webhooks before finding /users/me/webhooks
         let url = format!("{}/users/me/webhooks", self.url_prefix);
-        let resp = self.client.get(&url).send().await?;
+        let resp = self.send(self.client.get(&url)).await?;
         self.json(resp).await
     }
 
@@ -562,7 +1543,7 @@ impl ApiClient {
         val: Value,
     ) -> Result<Entry, Error> {
         let url = format!("{}/lists/{}/entries/{}", self.url_prefix, list_id, entry_id);
-        let resp = self.client.put(&url).json(&val).send().await?;
+        let resp = self.send(self.client.put(&url).json(&val)).await?;
         self.json(resp).await
     }
 
@@ -573,7 +1554,7 @@ impl ApiClient {
         comment: &NewComment,
     ) -> Result<Activity, Error> {
         let url = format!("{}/users/me/lists/{}/activities", self.url_prefix, list_id);
-        let resp = self.client.post(&url).json(&comment).send().await?;
+        let resp = self.send(self.client.post(&url).json(&comment)).await?;
         self.json(resp).await
     }
 
@@ -588,9 +1569,224 @@ impl ApiClient {
             "{}/users/me/lists/{}/entries/{}/activities",
             self.url_prefix, list_id, entry_id
         );
-        let resp = self.client.post(&url).json(&comment).send().await?;
+        let resp = self.send(self.client.post(&url).json(&comment)).await?;
         self.json(resp).await
     }
+
+    /// Changes the text of a comment (a list or entry comment, by activity id).
+    /// Returns the updated comment.
+    pub async fn update_comment(
+        &self,
+        list_id: ID,
+        activity_id: ID,
+        new_message: &str,
+    ) -> Result<Activity, Error> {
+        let url = format!(
+            "{}/users/me/lists/{}/activities/{}",
+            self.url_prefix, list_id, activity_id
+        );
+        let comment = NewComment {
+            message: new_message.to_string(),
+        };
+        let resp = self.send(self.client.put(&url).json(&comment)).await?;
+        self.json(resp).await
+    }
+
+    /// Deletes an activity of the list, such as a list or entry comment
+    pub async fn delete_activity(&self, list_id: ID, activity_id: ID) -> Result<(), Error> {
+        let url = format!(
+            "{}/users/me/lists/{}/activities/{}",
+            self.url_prefix, list_id, activity_id
+        );
+        let resp = self.send(self.client.delete(&url)).await?;
+        self.expect_no_content(resp).await
+    }
+
+    /// Returns all comments on a list (not including comments on its entries).
+    /// Comments are fetched in pages of 100, until a page is not full, so a comment
+    /// added while pages are being fetched may be returned twice.
+    /// To fetch one page at a time, see get_list_activities.
+    pub async fn get_list_comments(&self, list_id: ID) -> Result<Vec<Comment>, Error> {
+        let mut comments = Vec::new();
+        loop {
+            let page = self
+                .get_list_activities(
+                    list_id,
+                    ActivityFilter::Comments,
+                    ACTIVITY_PAGE_SIZE,
+                    comments.len(),
+                )
+                .await?;
+            let last_page = page.len() < ACTIVITY_PAGE_SIZE;
+            comments.extend(page);
+            if last_page {
+                return Ok(comments);
+            }
+        }
+    }
+
+    /// Returns activities of a list, such as comments and changes to entries,
+    /// optionally filtered by type. Field changes are in Activity.changed_data.
+    /// Returns up to limit activities, after skipping skip activities.
+    /// If limit is 0, limit and skip are not sent, and Zenkit's default limit applies.
+    pub async fn get_list_activities(
+        &self,
+        list_id: ID,
+        filter: ActivityFilter,
+        limit: usize,
+        skip: usize,
+    ) -> Result<Vec<Activity>, Error> {
+        let url = format!("{}/users/me/lists/{}/activities", self.url_prefix, list_id);
+        let mut req = self.client.get(&url).query(&[("filter", filter as u8)]);
+        if limit > 0 {
+            req = req.query(&[("limit", limit), ("skip", skip)]);
+        }
+        let resp = self.send(req).await?;
+        self.json(resp).await
+    }
+
+    /// Returns comments on a list entry
+    pub async fn get_entry_comments(
+        &self,
+        list_id: ID,
+        entry_id: ID,
+    ) -> Result<Vec<Comment>, Error> {
+        let url = format!(
+            "{}/users/me/lists/{}/entries/{}/activities",
+            self.url_prefix, list_id, entry_id
+        );
+        let resp = self
+            .send(
+                self.client
+                    .get(&url)
+                    .query(&[("filter", ActivityFilter::Comments as u8)]),
+            )
+            .await?;
+        self.json(resp).await
+    }
+
+    /// Returns comments on a list entry, each paired with its author.
+    /// Authors are looked up in the (cached) workspace user list, so repeat authors
+    /// share the same User object. If the author is no longer a workspace member,
+    /// the User is constructed from the name fields of the comment.
+    pub async fn get_entry_comments_with_authors(
+        &self,
+        list_id: ID,
+        entry_id: ID,
+    ) -> Result<Vec<(Comment, Arc<User>)>, Error> {
+        let comments = self.get_entry_comments(list_id, entry_id).await?;
+        let workspace = self.get_list_workspace(list_id).await?;
+        let users = self.get_users(workspace.id).await?;
+        let mut former_users: Vec<Arc<User>> = Vec::new();
+        let mut result = Vec::with_capacity(comments.len());
+        for comment in comments.into_iter() {
+            let author = match users
+                .iter()
+                .chain(former_users.iter())
+                .find(|u| u.id == comment.user_id)
+            {
+                Some(u) => u.clone(),
+                None => {
+                    let u = Arc::new(activity_user(&comment));
+                    former_users.push(u.clone());
+                    u
+                }
+            };
+            result.push((comment, author));
+        }
+        Ok(result)
+    }
+
+    /// Returns the name of the field changed by the activity, or None if the activity
+    /// did not change a field. If the activity's element_name is not set, the name is
+    /// looked up by changed_data_element_id in the list's fields (using the ListInfo cache).
+    pub async fn resolve_activity_field(
+        &self,
+        activity: &Activity,
+    ) -> Result<Option<String>, Error> {
+        if let Some(name) = &activity.element_name {
+            return Ok(Some(name.clone()));
+        }
+        let (list_id, element_id) = match (activity.list_id, activity.changed_data_element_id) {
+            (Some(list_id), Some(element_id)) => (list_id, element_id),
+            _ => return Ok(None),
+        };
+        let workspace_id = match activity.workspace_id {
+            Some(id) => id,
+            None => self.get_list_workspace(list_id).await?.id,
+        };
+        let list_info = self
+            .get_list_info(workspace_id, &list_id.to_string())
+            .await?;
+        Ok(list_info
+            .fields()
+            .iter()
+            .find(|f| f.id == element_id)
+            .map(|f| f.name.clone()))
+    }
+}
+
+/// Constructs User from the user fields of an activity.
+/// Used when the activity's user is not in the workspace user list.
+fn activity_user(activity: &Activity) -> User {
+    User {
+        id: activity.user_id,
+        short_id: String::new(),
+        uuid: String::new(),
+        display_name: activity.user_display_name.clone(),
+        full_name: activity.user_full_name.clone(),
+        initials: activity.user_initials.clone(),
+        user_name: activity.user_username.clone(),
+        background_id: None,
+        api_key: None,
+        image_link: None,
+        is_image_preferred: activity.user_is_image_preferred,
+        anonymous: None,
+        locale: None,
+        timezone: None,
+        is_super_admin: None,
+        registered_at: None,
+        trello_token: None,
+        settings: None,
+        email_count: 0,
+    }
+}
+
+/// List entries, deserialized one at a time as the response is received.
+/// Returned by ApiClient::get_list_entries_stream
+/// ```rust,no_run
+/// # async fn run(api: &zenkit::ApiClient) -> Result<(), zenkit::Error> {
+/// let mut entries = api.get_list_entries_stream(1234, &Default::default()).await?;
+/// while let Some(entry) = entries.next().await? {
+///     println!("{}", entry.id);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EntryStream {
+    resp: Response,
+    splitter: JsonArraySplitter,
+}
+
+impl EntryStream {
+    /// Returns the next entry, or None after the last entry
+    pub async fn next(&mut self) -> Result<Option<Entry>, Error> {
+        loop {
+            if let Some(bytes) = self.splitter.next_element()? {
+                return serde_json::from_slice(&bytes)
+                    .map(Some)
+                    .map_err(json_error::<Entry>);
+            }
+            if self.splitter.is_done() {
+                return Ok(None);
+            }
+            match self.resp.chunk().await? {
+                Some(chunk) => self.splitter.push(&chunk),
+                None => return Err(Error::Other("Unexpected end of response".to_string())),
+            }
+        }
+    }
 }
 
 // used internally for updateChecklists api
@@ -599,28 +1795,96 @@ struct UpdateChecklistParam {
     checklists: Vec<Checklist>,
 }
 
+// used internally for copy list api
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct CopyListParam<'a> {
+    name: &'a str,
+    should_copy_entries: bool,
+}
+
+// used internally for create workspace api
+#[derive(Serialize, Debug)]
+struct NewWorkspaceParam<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+}
+
+// used internally for bulk delete api
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DeleteEntriesParam<'a> {
+    list_entry_ids: &'a [ID],
+    should_delete_all: bool,
+}
+
 #[derive(Debug)]
 struct WorkspaceData {
     workspace: Arc<Workspace>,
+    /// whether workspace.lists was loaded with the workspace
+    with_lists: bool,
+    /// lists loaded on first use, if the workspace was loaded without lists
+    lists: RwLock<Option<Vec<List>>>,
     user_cache: RwLock<UserCache>,
 }
 
 impl WorkspaceData {
-    fn new(w: Workspace) -> Self {
+    fn new(w: Workspace, with_lists: bool) -> Self {
         Self {
             workspace: Arc::new(w),
+            with_lists,
+            lists: RwLock::new(None),
             user_cache: Default::default(),
         }
     }
 
+    /// Loads lists if the workspace was loaded without them, and they have not been loaded
+    async fn ensure_lists(&self, api: &ApiClient) -> Result<(), Error> {
+        if self.with_lists || self.lists.read()?.is_some() {
+            return Ok(());
+        }
+        let lists = api.get_workspace_lists_raw(self.workspace.id).await?;
+        *self.lists.write()? = Some(lists);
+        Ok(())
+    }
+
+    // Applies f to the loaded lists
+    fn map_lists<T, F: FnOnce(&[List]) -> T>(&self, f: F) -> Result<T, Error> {
+        if self.with_lists {
+            return Ok(f(&self.workspace.lists));
+        }
+        let read = self.lists.read()?;
+        Ok(f(read.as_deref().unwrap_or_default()))
+    }
+
+    /// Returns lists in workspace, loading them if needed
+    pub async fn lists(&self, api: &ApiClient) -> Result<Vec<List>, Error> {
+        self.ensure_lists(api).await?;
+        self.map_lists(|lists| lists.to_vec())
+    }
+
+    /// Returns the list with the given name, id, or uuid, loading lists if needed
+    pub async fn find_list(
+        &self,
+        api: &ApiClient,
+        list_allid: &str,
+    ) -> Result<Option<List>, Error> {
+        self.ensure_lists(api).await?;
+        self.map_lists(|lists| lists.iter().find(|l| l.has_id(list_allid)).cloned())
+    }
+
     /// Returns user cache; loads users if cache has not been initialized,
     /// or if force_reload is true
-    pub async fn ensure_user_cache(&self, force_reload: bool) -> Result<(), Error> {
+    pub async fn ensure_user_cache(
+        &self,
+        api: &ApiClient,
+        force_reload: bool,
+    ) -> Result<(), Error> {
         let mut write = self.user_cache.write()?;
         if write.is_empty() || force_reload {
             write.replace_all(
-                crate::get_api()?
-                    .get_users_raw(self.workspace.id)
+                api.get_users_raw(self.workspace.id)
                     .await?
                     .into_iter()
                     .map(Arc::new)
@@ -631,19 +1895,96 @@ impl WorkspaceData {
     }
 
     /// Returns list of users in workspace
-    pub async fn users(&self) -> Result<Vec<Arc<User>>, Error> {
-        self.ensure_user_cache(false).await?;
+    pub async fn users(&self, api: &ApiClient) -> Result<Vec<Arc<User>>, Error> {
+        self.ensure_user_cache(api, false).await?;
         let read = self.user_cache.read()?;
         Ok(read.users())
     }
 
     /// Find first user matching predicate
-    pub async fn find_user<P>(&self, predicate: P) -> Result<Option<Arc<User>>, Error>
+    pub async fn find_user<P>(
+        &self,
+        api: &ApiClient,
+        predicate: P,
+    ) -> Result<Option<Arc<User>>, Error>
     where
         P: Fn(&Arc<User>) -> bool,
     {
-        self.ensure_user_cache(false).await?;
+        self.ensure_user_cache(api, false).await?;
         let read = self.user_cache.read()?;
         Ok(read.find_user(predicate))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{next_backoff, ApiClient, ApiConfig, MAX_RETRY_BACKOFF};
+    use crate::types::File;
+    use std::time::Duration;
+
+    fn test_file(url: &str, provider: &str) -> File {
+        let mut json = crate::list::test::test_file(1, 3, "a.txt", url);
+        json["provider"] = provider.into();
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_file_request_auth() {
+        let api = ApiClient::new(ApiConfig {
+            token: "secret".to_string(),
+            endpoint: "https://zenkit.com/api/v1".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let has_token = |url: &str, provider: &str| {
+            let req = api.file_request(&test_file(url, provider)).unwrap();
+            req.headers().contains_key("Zenkit-API-Key")
+        };
+        assert!(has_token("https://zenkit.com/api/v1/files/1", "zenkit"));
+        assert!(has_token("https://zenkit.com:443/uploads/a.txt", "zenkit"));
+        assert!(!has_token("https://example.com/a.txt", "zenkit"));
+        assert!(!has_token("https://drive.google.com/file/1", "google"));
+        assert!(!has_token("http://zenkit.com/api/v1/files/1", "zenkit"));
+        assert!(!has_token("https://zenkit.com:8443/files/1", "zenkit"));
+        assert!(!has_token("https://zenkit.com.example.com/a.txt", "link"));
+    }
+
+    #[test]
+    fn test_file_request_timeout() {
+        let api = ApiClient::new(ApiConfig {
+            token: "secret".to_string(),
+            transfer_timeout: Duration::from_secs(600),
+            ..Default::default()
+        })
+        .unwrap();
+        let req = api
+            .file_request(&test_file("https://zenkit.com/api/v1/files/1", "zenkit"))
+            .unwrap();
+        assert_eq!(req.timeout(), Some(&Duration::from_secs(600)));
+
+        // a supplied client's timeout is not overridden
+        let api = ApiClient::new(ApiConfig {
+            token: "secret".to_string(),
+            client: Some(reqwest::Client::new()),
+            ..Default::default()
+        })
+        .unwrap();
+        let req = api
+            .file_request(&test_file("https://zenkit.com/api/v1/files/1", "zenkit"))
+            .unwrap();
+        assert_eq!(req.timeout(), None);
+    }
+
+    #[test]
+    fn test_next_backoff() {
+        let mut backoff = Duration::from_secs(1);
+        for _ in 0..100 {
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(backoff, MAX_RETRY_BACKOFF);
+        assert_eq!(next_backoff(Duration::from_secs(2)), Duration::from_secs(4));
+        let long = Duration::from_secs(600);
+        assert_eq!(next_backoff(long), long);
+        assert_eq!(next_backoff(Duration::MAX), Duration::MAX);
+    }
+}
@@ -3,8 +3,8 @@
 //! and force its timezone to Utc for consitency. The application can easily
 //! change timezeone by calling with_timezone(tz).
 
-// use and re-export the Utc timezone
-pub use chrono::Utc;
+// use and re-export the Utc timezone, and NaiveDate for date-only values
+pub use chrono::{NaiveDate, Utc};
 use serde::{de, Serialize};
 use std::{fmt, ops::Deref, str::FromStr};
 
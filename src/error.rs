@@ -18,6 +18,12 @@ pub enum Error {
     IoError(String),
     /// Error returned by reqwest library
     Reqwest(reqwest::Error),
+    /// Zenkit is unavailable (http 503), for example, during maintenance.
+    /// retry_after is the delay advised by the Retry-After header, if present.
+    ServiceUnavailable {
+        /// delay before the request should be retried
+        retry_after: Option<std::time::Duration>,
+    },
 
     /// assumed single-value category field but multiple values were set
     /// First param is general message, second is field name
@@ -26,6 +32,9 @@ pub enum Error {
     /// Api token has not been set
     MissingApiToken(String),
 
+    /// File has no url (File.file_url is None). Parameter is the file id.
+    MissingFileUrl(crate::types::ID),
+
     /// Error if static object is already initialized
     AlreadyInitialized,
     /// Error if static initializer was not called before getter
@@ -45,6 +54,12 @@ impl Error {
         }
         false
     }
+
+    /// Returns true if the error is a request or connection timeout
+    /// (see ApiConfig.timeout and connect_timeout)
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Error::Reqwest(e) if e.is_timeout())
+    }
 }
 
 impl std::error::Error for Error {}
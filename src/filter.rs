@@ -0,0 +1,280 @@
+//! Builder for filter expressions, used as the `filter` parameter of
+//! GetEntriesRequest and GetEntriesViewRequest.
+//!
+//! ```rust
+//! use zenkit::filter::FilterBuilder;
+//! // top-level items of a hierarchy list, where 1234 is the hierarchy field id
+//! let filter = FilterBuilder::new().level(1234, 0).build();
+//! // items with a value from 1 to 10 in field 56, or in category 7 of field 89
+//! let filter = FilterBuilder::new()
+//!     .number_range(56, 1.0, 10.0)
+//!     .or(FilterBuilder::new().category_in(89, &[7]))
+//!     .build();
+//! ```
+
+use crate::types::{DateFilterTermModus, DateTime, FilterKeys, FilterTermModus, JsonMap, Utc, ID};
+use serde_json::{json, Value};
+
+/// Builds a filter expression for list entry queries.
+/// Terms added to a builder must all match, unless the builder was combined with `or`.
+#[derive(Debug, Default)]
+pub struct FilterBuilder {
+    op: Combinator,
+    terms: Vec<Value>,
+}
+
+/// How the terms of a filter expression are combined
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum Combinator {
+    #[default]
+    And,
+    Or,
+}
+
+impl Combinator {
+    fn key(self) -> &'static str {
+        match self {
+            Combinator::And => "AND",
+            Combinator::Or => "OR",
+        }
+    }
+}
+
+impl FilterBuilder {
+    /// Constructs an empty filter (matches all entries)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts hierarchy items to those at the given depth.
+    /// element_id is the id of the hierarchy field. Top-level items have depth 0.
+    pub fn level(mut self, element_id: ID, depth: u32) -> Self {
+        self.terms
+            .push(filter_term(element_id, FilterKeys::Level, json!(depth)));
+        self
+    }
+
+    /// Restricts a text field to values matching text according to modus,
+    /// for example, FilterTermModus::Contains or StartsWith.
+    pub fn text<S: Into<String>>(
+        mut self,
+        element_id: ID,
+        modus: FilterTermModus,
+        text: S,
+    ) -> Self {
+        let mut term = filter_term(element_id, FilterKeys::Text, json!(text.into()));
+        if let Value::Object(map) = &mut term {
+            map.insert("modus".to_string(), json!(modus));
+        }
+        self.terms.push(term);
+        self
+    }
+
+    /// Restricts a number field to the range from..=to
+    pub fn number_range(mut self, element_id: ID, from: f64, to: f64) -> Self {
+        let mut term = filter_term(element_id, FilterKeys::NumberFrom, json!(from));
+        if let Value::Object(map) = &mut term {
+            map.insert(key_name(FilterKeys::NumberTo), json!(to));
+            map.insert("modus".to_string(), json!(FilterTermModus::InRange));
+        }
+        self.terms.push(term);
+        self
+    }
+
+    /// Restricts a categories (label) field to entries with any of the category ids
+    pub fn category_in(mut self, element_id: ID, ids: &[ID]) -> Self {
+        self.terms.push(filter_term(
+            element_id,
+            FilterKeys::FilterCategories,
+            json!(ids),
+        ));
+        self
+    }
+
+    /// Returns a filter matching entries that match both this filter and other
+    pub fn and(self, other: FilterBuilder) -> Self {
+        self.combine(Combinator::And, other)
+    }
+
+    /// Returns a filter matching entries that match either this filter or other
+    pub fn or(self, other: FilterBuilder) -> Self {
+        self.combine(Combinator::Or, other)
+    }
+
+    // Combines two filters. Groups with a single term, or combined with the same
+    // operator, are merged rather than nested.
+    fn combine(self, op: Combinator, other: FilterBuilder) -> Self {
+        let mut terms = Vec::new();
+        for group in [self, other] {
+            if group.op == op || group.terms.len() == 1 {
+                terms.extend(group.terms);
+            } else if !group.terms.is_empty() {
+                terms.push(group.build());
+            }
+        }
+        // a single term is the same with either operator
+        let op = if terms.len() > 1 { op } else { Combinator::And };
+        Self { op, terms }
+    }
+
+    /// Restricts a date field to the custom range from..=to.
+    /// Both boundaries are inclusive.
+    pub fn date_range(mut self, element_id: ID, from: DateTime<Utc>, to: DateTime<Utc>) -> Self {
+        let mut term = filter_term(
+            element_id,
+            FilterKeys::DateType,
+            json!(DateFilterTermModus::Custom),
+        );
+        if let Value::Object(map) = &mut term {
+            map.insert(key_name(FilterKeys::DateFrom), json!(from));
+            map.insert(key_name(FilterKeys::DateTo), json!(to));
+        }
+        self.terms.push(term);
+        self
+    }
+
+    /// Restricts results to the entry with the given id.
+    /// Entry id and uuid are entry properties rather than list elements,
+    /// so these terms have no elementId.
+    pub fn id_equals(mut self, id: ID) -> Self {
+        self.terms
+            .push(json!({ "modus": FilterTermModus::Equals, "id": id }));
+        self
+    }
+
+    /// Restricts results to the entry with the given uuid
+    pub fn uuid_equals<S: Into<String>>(mut self, uuid: S) -> Self {
+        self.terms
+            .push(json!({ "modus": FilterTermModus::Equals, "uuid": uuid.into() }));
+        self
+    }
+
+    /// Returns the filter expression
+    pub fn build(self) -> Value {
+        if self.terms.is_empty() {
+            return Value::Object(JsonMap::new());
+        }
+        json!({ self.op.key(): { "TERMS": self.terms } })
+    }
+}
+
+/// Returns the json name of the filter key
+fn key_name(key: FilterKeys) -> String {
+    serde_json::to_value(key)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// Constructs a single filter term for the element
+fn filter_term(element_id: ID, key: FilterKeys, val: Value) -> Value {
+    let mut term = JsonMap::new();
+    term.insert("elementId".to_string(), json!(element_id));
+    term.insert(key_name(key), val);
+    Value::Object(term)
+}
+
+#[cfg(test)]
+mod test {
+    use super::FilterBuilder;
+    use crate::types::{DateTime, FilterTermModus, Utc};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_empty_filter() {
+        assert_eq!(FilterBuilder::new().build(), json!({}));
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(
+            FilterBuilder::new().level(12, 0).build(),
+            json!({ "AND": { "TERMS": [ { "elementId": 12, "level": 0 } ] } })
+        );
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            FilterBuilder::new()
+                .text(3, FilterTermModus::Contains, "abc")
+                .build(),
+            json!({ "AND": { "TERMS": [
+                { "elementId": 3, "text": "abc", "modus": "contains" }
+            ] } })
+        );
+    }
+
+    #[test]
+    fn test_number_range_and_categories() {
+        assert_eq!(
+            FilterBuilder::new()
+                .number_range(4, 1.0, 10.0)
+                .category_in(6, &[7, 8])
+                .build(),
+            json!({ "AND": { "TERMS": [
+                { "elementId": 4, "numberFrom": 1.0, "numberTo": 10.0, "modus": "inRange" },
+                { "elementId": 6, "filterCategories": [7, 8] },
+            ] } })
+        );
+    }
+
+    #[test]
+    fn test_and_or() {
+        let a = || FilterBuilder::new().category_in(6, &[7]);
+        let b = || FilterBuilder::new().level(12, 0);
+        assert_eq!(
+            a().or(b()).build(),
+            json!({ "OR": { "TERMS": [
+                { "elementId": 6, "filterCategories": [7] },
+                { "elementId": 12, "level": 0 },
+            ] } })
+        );
+        assert_eq!(a().and(b()).build(), a().level(12, 0).build());
+        // an AND group within OR is nested
+        assert_eq!(
+            a().level(12, 0).or(b()).build(),
+            json!({ "OR": { "TERMS": [
+                { "AND": { "TERMS": [
+                    { "elementId": 6, "filterCategories": [7] },
+                    { "elementId": 12, "level": 0 },
+                ] } },
+                { "elementId": 12, "level": 0 },
+            ] } })
+        );
+        assert_eq!(FilterBuilder::new().or(b()).build(), b().build());
+    }
+
+    #[test]
+    fn test_id_equals() {
+        assert_eq!(
+            FilterBuilder::new().id_equals(42).build(),
+            json!({ "AND": { "TERMS": [ { "modus": "equals", "id": 42 } ] } })
+        );
+        assert_eq!(
+            FilterBuilder::new()
+                .uuid_equals("00000000-0000-0000-0000-000000000000")
+                .build(),
+            json!({ "AND": { "TERMS": [ {
+                "modus": "equals",
+                "uuid": "00000000-0000-0000-0000-000000000000"
+            } ] } })
+        );
+    }
+
+    #[test]
+    fn test_date_range() {
+        let from = DateTime::<Utc>::from_str("2021-03-01").unwrap();
+        let to = DateTime::<Utc>::from_str("2021-03-31T23:59:59Z").unwrap();
+        assert_eq!(
+            FilterBuilder::new().date_range(5, from, to).build(),
+            json!({ "AND": { "TERMS": [ {
+                "elementId": 5,
+                "dateType": 10,
+                "dateFrom": "2021-03-01T00:00:00Z",
+                "dateTo": "2021-03-31T23:59:59Z"
+            } ] } })
+        );
+    }
+}
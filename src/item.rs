@@ -1,8 +1,17 @@
 use crate::{
-    types::{Entry, Field, ID, UUID},
+    apiclient::ApiClient,
+    types::{
+        ElementCategoryId, Entry, Field, File, NaiveDate, NumericType, User, ZKObjectID, ID, UUID,
+    },
     Error,
 };
-use std::{iter::Iterator, string::ToString};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    iter::Iterator,
+    string::ToString,
+    sync::{Arc, Weak},
+};
 
 /// Item in a list. Wrapper for Entry that includes field getters for business values.
 #[derive(Debug)]
@@ -10,7 +19,10 @@ pub struct Item<'li> {
     entry: Entry,
     list_name: &'li str,
     list_id: ID,
+    workspace_id: ID,
     fields: &'li [Field],
+    task_field: Option<&'li Field>,
+    api: &'li Weak<ApiClient>,
 }
 
 impl<'li> Item<'li> {
@@ -19,26 +31,49 @@ impl<'li> Item<'li> {
         entry: Entry,
         list_name: &'li str,
         list_id: ID,
+        workspace_id: ID,
         fields: &'li [Field],
+        task_field: Option<&'li Field>,
+        api: &'li Weak<ApiClient>,
     ) -> Self {
         Self {
             entry,
             list_name,
             list_id,
+            workspace_id,
             fields,
+            task_field,
+            api,
         }
     }
 
+    /// Returns the client that loaded this item
+    fn api(&self) -> Result<Arc<ApiClient>, Error> {
+        crate::apiclient::upgrade(self.api)
+    }
+
     /// Returns reference to the inner entry. Also available with deref.
     pub fn as_entry(&self) -> &Entry {
         &self.entry
     }
 
+    /// Re-fetches the entry from Zenkit, for example, to update comment_count after adding
+    /// a comment. Only the wrapped entry is replaced; field definitions are unchanged.
+    pub async fn refresh(&mut self) -> Result<(), Error> {
+        self.entry = self.api()?.get_entry(self.list_id, self.entry.id).await?;
+        Ok(())
+    }
+
     /// Return entry id
     pub fn get_id(&self) -> ID {
         self.entry.id
     }
 
+    /// Returns true if the entry has been saved in Zenkit (has a server-assigned id)
+    pub fn is_persisted(&self) -> bool {
+        self.entry.id != 0
+    }
+
     /// Returns entry uuid
     pub fn get_uuid(&self) -> &UUID {
         &self.entry.uuid
@@ -57,6 +92,61 @@ impl<'li> Item<'li> {
             })
     }
 
+    /// Returns the list's primary field (definition), or None if the list has no primary field
+    pub fn primary_field(&self) -> Option<&Field> {
+        self.fields.iter().find(|f| f.is_primary)
+    }
+
+    /// Returns the item's title: the value of the primary field, formatted according to
+    /// the field's type. Falls back to the entry's display_string if the primary field
+    /// has no value or is of a type that can't be formatted.
+    pub fn title(&self) -> String {
+        let field = match self.primary_field() {
+            Some(field) => field,
+            None => return self.entry.display_string.clone(),
+        };
+        let uuid = &field.uuid;
+        let value = match field.element_category {
+            ElementCategoryId::Text | ElementCategoryId::URL => self
+                .entry
+                .get_text_value(uuid)
+                .ok()
+                .flatten()
+                .map(String::from),
+            ElementCategoryId::Number => match field.numeric_type() {
+                Some(NumericType::Integer) => self
+                    .entry
+                    .get_int_value(uuid)
+                    .ok()
+                    .flatten()
+                    .map(|n| n.to_string()),
+                _ => self
+                    .entry
+                    .get_float_value(uuid)
+                    .ok()
+                    .flatten()
+                    .map(|n| n.to_string()),
+            },
+            ElementCategoryId::Date => self
+                .entry
+                .get_date_value(uuid)
+                .ok()
+                .flatten()
+                .map(String::from),
+            ElementCategoryId::Categories => {
+                Some(crate::join(", ", &self.entry.get_category_names(uuid)))
+            }
+            ElementCategoryId::Persons => {
+                Some(crate::join(", ", &self.entry.get_person_names(uuid)))
+            }
+            ElementCategoryId::Formula => self.get_formula_text(uuid).ok().flatten(),
+            _ => None,
+        };
+        value
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| self.entry.display_string.clone())
+    }
+
     /// Returns value of text field. or None if not defined
     /// fname parameter may be field name, id, or uuid
     pub fn get_text_value(&self, fname: &str) -> Result<Option<&str>, Error> {
@@ -78,6 +168,13 @@ impl<'li> Item<'li> {
             .map(|field| self.entry.get_float_value(&field.uuid))?
     }
 
+    /// Returns value of checkbox field. or None if not defined (unchecked boxes may have no value).
+    /// fname parameter may be field name, id, or uuid
+    pub fn get_bool_value(&self, fname: &str) -> Result<Option<bool>, Error> {
+        self.get_field(fname)
+            .map(|field| self.entry.get_bool_value(&field.uuid))?
+    }
+
     /// Returns value of date field. or None if not defined
     /// fname parameter may be field name, id, or uuid
     pub fn get_date_value(&self, fname: &str) -> Result<Option<&str>, Error> {
@@ -85,6 +182,39 @@ impl<'li> Item<'li> {
             .map(|field| self.entry.get_date_value(&field.uuid))?
     }
 
+    /// Returns value of date field as a date without time, or None if not defined.
+    /// Any time component of the value is ignored.
+    /// fname parameter may be field name, id, or uuid
+    pub fn get_naive_date(&self, fname: &str) -> Result<Option<NaiveDate>, Error> {
+        match self.get_date_value(fname)? {
+            Some(s) => NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| Error::Other(format!("Invalid date '{}' for field {}", s, fname))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the depth of the item in a hierarchy list (0 for top-level items),
+    /// or None if not available. See Entry::hierarchy_level.
+    /// fname parameter may be field name, id, or uuid of the hierarchy field
+    pub fn get_hierarchy_level(&self, fname: &str) -> Result<Option<u32>, Error> {
+        self.get_field(fname)
+            .map(|field| self.entry.hierarchy_level(&field.uuid))
+    }
+
+    /// Returns the computed value of a formula field, formatted as a string,
+    /// or None if there is no value. See Entry::get_formula_value.
+    /// fname parameter may be field name, id, or uuid
+    pub fn get_formula_text(&self, fname: &str) -> Result<Option<String>, Error> {
+        let field = self.get_field(fname)?;
+        Ok(match self.entry.get_formula_value(&field.uuid)? {
+            Some(Value::String(s)) => Some(s),
+            Some(Value::Number(n)) => Some(n.to_string()),
+            Some(Value::Bool(b)) => Some(b.to_string()),
+            _ => None,
+        })
+    }
+
     /// Returns display names of persons in field value.
     /// fname parameter may be field name, id, or uuid
     pub fn get_person_names(&self, fname: &str) -> Result<Vec<&str>, Error> {
@@ -99,6 +229,21 @@ impl<'li> Item<'li> {
             .map(|field| self.entry.get_person_ids(&field.uuid))
     }
 
+    /// Returns users in a persons field.
+    /// Zenkit has no option to expand related objects in entries: persons fields
+    /// contain only each person's id and names. Full User objects are taken from
+    /// the (cached) workspace user list, so at most one request is made per workspace.
+    /// Persons no longer in the workspace are omitted.
+    /// fname parameter may be field name, id, or uuid
+    pub async fn get_persons(&self, fname: &str) -> Result<Vec<Arc<User>>, Error> {
+        let ids = self.get_person_ids(fname)?;
+        let users = self.api()?.get_users(self.workspace_id).await?;
+        Ok(ids
+            .iter()
+            .filter_map(|id| users.iter().find(|u| u.id == *id).cloned())
+            .collect())
+    }
+
     /// Returns uuids of referred objects in field value.
     /// fname parameter may be field name, id, or uuid
     pub fn get_references(&self, fname: &str) -> Result<Vec<&str>, Error> {
@@ -106,6 +251,13 @@ impl<'li> Item<'li> {
             .map(|field| self.entry.get_references(&field.uuid))
     }
 
+    /// Returns files attached in a files field.
+    /// fname parameter may be field name, id, or uuid
+    pub fn get_files(&self, fname: &str) -> Result<Vec<File>, Error> {
+        self.get_field(fname)
+            .map(|field| self.entry.get_files(&field.uuid))?
+    }
+
     /// Returns array of choice (aka label/category) values.
     /// Array could be empty if none are selected
     pub fn get_choices(&self, fname: &str) -> Result<Vec<&str>, Error> {
@@ -129,6 +281,52 @@ impl<'li> Item<'li> {
             )),
         }
     }
+
+    /// Returns whether the task is done, for items of task lists.
+    /// Returns None if the list is not a task list, or the task element is
+    /// a categories field without a "Done" choice.
+    pub fn is_done(&self) -> Option<bool> {
+        let field = self.task_field?;
+        match field.element_category {
+            ElementCategoryId::Checkbox => {
+                Some(self.entry.get_checked(&field.uuid).unwrap_or(false))
+            }
+            ElementCategoryId::Categories => {
+                let done = field.done_category()?;
+                Some(self.entry.get_category_ids(&field.uuid).contains(&done.id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the user that created the entry.
+    /// Returns None if the user is not a member of the list's workspace.
+    pub async fn created_by_user(&self) -> Result<Option<Arc<User>>, Error> {
+        self.find_workspace_user(self.entry.created_by).await
+    }
+
+    /// Returns the user that last updated the entry.
+    /// Returns None if the user is not a member of the list's workspace.
+    pub async fn updated_by_user(&self) -> Result<Option<Arc<User>>, Error> {
+        self.find_workspace_user(self.entry.updated_by).await
+    }
+
+    /// Returns the user that deprecated (deleted) the entry.
+    /// Returns None if the entry is not deprecated, or if the user is not
+    /// a member of the list's workspace.
+    pub async fn deprecated_by_user(&self) -> Result<Option<Arc<User>>, Error> {
+        match self.entry.deprecated_by {
+            Some(user_id) => self.find_workspace_user(user_id).await,
+            None => Ok(None),
+        }
+    }
+
+    // Looks up user in the (cached) user list of the item's workspace
+    async fn find_workspace_user(&self, user_id: ID) -> Result<Option<Arc<User>>, Error> {
+        self.api()?
+            .find_user(self.workspace_id, |u| u.id == user_id)
+            .await
+    }
 }
 
 impl<'li> std::ops::Deref for Item<'li> {
@@ -138,3 +336,89 @@ impl<'li> std::ops::Deref for Item<'li> {
         &self.entry
     }
 }
+
+/// Lightweight reference to a list item, for caching or passing around
+/// without retaining the full Entry.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ItemRef {
+    /// entry id
+    pub id: ID,
+    /// entry uuid
+    pub uuid: UUID,
+    /// entry title
+    pub display_string: String,
+    /// id of list containing the entry
+    pub list_id: ID,
+}
+
+impl ZKObjectID for ItemRef {
+    fn get_id(&self) -> ID {
+        self.id
+    }
+    fn get_uuid(&self) -> &UUID {
+        &self.uuid
+    }
+}
+
+impl From<&Entry> for ItemRef {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            id: entry.id,
+            uuid: entry.uuid.clone(),
+            display_string: entry.display_string.clone(),
+            list_id: entry.list_id,
+        }
+    }
+}
+
+impl From<&Item<'_>> for ItemRef {
+    fn from(item: &Item<'_>) -> Self {
+        ItemRef::from(item.as_entry())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::list::test::{
+        test_entry, test_field, test_file, test_list_info, test_list_info_with, test_task_list_info,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_is_done() {
+        let done = json!({ "00000000-0000-0000-0000-000000000004_checked": true });
+        let li = test_list_info();
+        assert_eq!(li.new_item(test_entry(done.clone())).is_done(), None);
+        let li = test_task_list_info();
+        assert_eq!(li.new_item(test_entry(done)).is_done(), Some(true));
+        assert_eq!(li.new_item(test_entry(json!({}))).is_done(), Some(false));
+    }
+
+    #[test]
+    fn test_get_bool_value() {
+        let li = test_task_list_info();
+        let item = li.new_item(test_entry(
+            json!({ "00000000-0000-0000-0000-000000000004_checked": true }),
+        ));
+        assert_eq!(item.get_bool_value("Done").unwrap(), Some(true));
+        assert_eq!(item.get_bool_value("Name").unwrap(), None);
+        assert!(item.get_bool_value("Missing").is_err());
+        let item = li.new_item(test_entry(json!({})));
+        assert_eq!(item.get_bool_value("Done").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_files() {
+        let li = test_list_info_with(vec![test_field(9, "Attachments", 15, json!({}))]);
+        let item = li.new_item(test_entry(json!({
+            "00000000-0000-0000-0000-000000000009_files": [
+                test_file(7, 9, "report.pdf", "https://example.com/report.pdf"),
+            ],
+        })));
+        let files = item.get_files("Attachments").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].file_name, "report.pdf");
+        assert_eq!(files[0].size, None);
+        assert!(item.get_files("Name").unwrap().is_empty());
+    }
+}
@@ -1,13 +1,15 @@
 //! Rust client api for Zenkit
 #![deny(missing_docs)]
 mod apiclient;
-pub use apiclient::{ApiClient, ApiConfig};
+pub use apiclient::{ApiClient, ApiConfig, EntryStream};
 mod error;
 pub use error::Error;
 mod errorcode;
 pub use errorcode::lookup_error;
+pub mod filter;
 mod item;
 mod list;
+pub mod query;
 pub mod types;
 mod user;
 pub(crate) use user::UserCache;
@@ -15,27 +17,116 @@ pub(crate) mod datetime;
 mod util;
 pub(crate) use util::{f32_or_str, join};
 
-use once_cell::sync::OnceCell;
-static API: OnceCell<ApiClient> = OnceCell::new();
+/// Result type returned by this crate's fallible functions
+pub type Result<T> = std::result::Result<T, Error>;
 
-/// First-time initialization of Zenkit api client.
-/// If api was already initialized, returns Error::AlreadyInitialized
+/// First-time initialization of the global Zenkit api client.
+/// If api was already initialized, returns Error::AlreadyInitialized.
+/// The global client is a convenience for applications that use a single client;
+/// to use more than one, construct each with ApiClient::new.
 /// ```rust
 /// use zenkit::{init_api,ApiConfig};
 /// let api = init_api(ApiConfig::default()).unwrap();
 /// ```
-pub fn init_api(config: ApiConfig) -> Result<&'static ApiClient, Error> {
-    let api = ApiClient::new(config)?;
-    API.set(api).map_err(|_| Error::AlreadyInitialized)?;
-    get_api()
+pub fn init_api(config: ApiConfig) -> Result<&'static ApiClient> {
+    global::set(ApiClient::new(config)?)
 }
 
 /// Returns API handle, or error if not initialized
-pub fn get_api() -> Result<&'static ApiClient, Error> {
-    API.get().ok_or(Error::NotInitialized)
+pub fn get_api() -> Result<&'static ApiClient> {
+    global::get()
+}
+
+/// Returns true if the api client has been initialized with init_api
+pub fn is_initialized() -> bool {
+    global::get().is_ok()
+}
+
+/// Clears the api client, so init_api can be called again, for example,
+/// to use a different endpoint in each test. Clients returned by earlier calls
+/// to get_api remain usable, and are not freed.
+/// Available in tests, or with the "test-util" feature.
+#[cfg(any(test, feature = "test-util"))]
+pub fn reset_api() {
+    global::clear()
+}
+
+// Storage for the global client, which can only be set once
+#[cfg(not(any(test, feature = "test-util")))]
+mod global {
+    use crate::{ApiClient, Error, Result};
+    use once_cell::sync::OnceCell;
+    use std::sync::Arc;
+
+    static API: OnceCell<Arc<ApiClient>> = OnceCell::new();
+
+    pub(crate) fn set(api: Arc<ApiClient>) -> Result<&'static ApiClient> {
+        API.set(api).map_err(|_| Error::AlreadyInitialized)?;
+        get()
+    }
+
+    pub(crate) fn get() -> Result<&'static ApiClient> {
+        API.get()
+            .map(|api| api.as_ref())
+            .ok_or(Error::NotInitialized)
+    }
+}
+
+// Storage for the global client, which can be cleared with reset_api.
+// Each client is leaked when it is set, so references returned by get_api
+// remain valid after reset_api.
+#[cfg(any(test, feature = "test-util"))]
+mod global {
+    use crate::{ApiClient, Error, Result};
+    use once_cell::sync::Lazy;
+    use std::sync::{Arc, RwLock};
+
+    static API: Lazy<RwLock<Option<&'static ApiClient>>> = Lazy::new(|| RwLock::new(None));
+
+    pub(crate) fn set(api: Arc<ApiClient>) -> Result<&'static ApiClient> {
+        let mut cell = API.write()?;
+        if cell.is_some() {
+            return Err(Error::AlreadyInitialized);
+        }
+        let api: &'static Arc<ApiClient> = Box::leak(Box::new(api));
+        let api: &'static ApiClient = api;
+        *cell = Some(api);
+        Ok(api)
+    }
+
+    pub(crate) fn get() -> Result<&'static ApiClient> {
+        API.read()?.ok_or(Error::NotInitialized)
+    }
+
+    pub(crate) fn clear() {
+        if let Ok(mut api) = API.write() {
+            *api = None;
+        }
+    }
 }
 
 /// Zenkit API common Traits and structs
 pub mod prelude {
     pub use crate::types::{DateTime, Utc, ZKObjectID};
 }
+
+#[cfg(test)]
+mod test {
+    use super::{init_api, is_initialized, reset_api, ApiConfig, Error};
+
+    #[test]
+    fn test_reset_api() {
+        let config = || ApiConfig {
+            token: "test".to_string(),
+            ..Default::default()
+        };
+        reset_api();
+        assert!(!is_initialized());
+        assert!(init_api(config()).is_ok());
+        assert!(is_initialized());
+        assert!(matches!(init_api(config()), Err(Error::AlreadyInitialized)));
+        reset_api();
+        assert!(init_api(config()).is_ok());
+        reset_api();
+    }
+}
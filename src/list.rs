@@ -3,14 +3,32 @@
 //!
 
 use crate::{
+    filter::FilterBuilder,
+    query::Query,
     types::{
-        AllId, ElementCategoryId, Entry, Field, GetEntriesRequest, Item, JsonMap, List,
-        NumericType, TextFormat, UpdateAction, ID, UUID,
+        AllId, DateTime, ElementCategoryId, Entry, Field, FilterCountData, FilterTermModus,
+        GetEntriesRequest, GetEntriesViewRequest, Item, JsonMap, List, NaiveDate, NumericType,
+        OrderBy, SortDirection, TextFormat, UpdateAction, Utc, ID, UUID,
     },
-    Error,
+    ApiClient, Error,
 };
+use futures_util::stream::Stream;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::{fmt, iter::Iterator, rc::Rc, string::ToString};
+use std::{
+    collections::HashMap,
+    fmt,
+    iter::Iterator,
+    rc::Rc,
+    string::ToString,
+    sync::{Arc, Weak},
+};
+
+/// Maximum number of entries per page
+const MAX_PAGE_SIZE: usize = 1000;
+
+/// Maximum number of pages fetched concurrently by get_items_parallel
+const MAX_CONCURRENT_PAGES: usize = 8;
 
 /// A read-only reference to a List and its fields
 /// To modify list field definitions, use methods of workspace ..
@@ -18,6 +36,7 @@ use std::{fmt, iter::Iterator, rc::Rc, string::ToString};
 pub struct ListInfo {
     list: List,
     fields: Vec<Field>,
+    api: Weak<ApiClient>,
 }
 
 impl fmt::Display for ListInfo {
@@ -35,8 +54,35 @@ impl fmt::Display for ListInfo {
 }
 
 impl ListInfo {
-    pub(crate) fn new(list: List, fields: Vec<Field>) -> Self {
-        ListInfo { list, fields }
+    pub(crate) fn new(list: List, fields: Vec<Field>, api: Weak<ApiClient>) -> Self {
+        ListInfo { list, fields, api }
+    }
+
+    /// Returns the client that loaded this list
+    pub(crate) fn api(&self) -> Result<Arc<ApiClient>, Error> {
+        crate::apiclient::upgrade(&self.api)
+    }
+
+    /// Returns the element holding the todo/done state, if this is a task list
+    /// (list.settings.tasks is set). See also Item::is_done.
+    pub fn task_element(&self) -> Option<&Field> {
+        let element_id = self.list.settings.as_ref()?.tasks.as_ref()?.element_id?;
+        self.fields.iter().find(|f| f.id == element_id)
+    }
+
+    /// Returns the hierarchy field, if this is a hierarchy list
+    pub fn hierarchy_field(&self) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|f| f.element_category == ElementCategoryId::Hierarchy)
+    }
+
+    /// Returns the uuid of the sub-list of a hierarchy list
+    pub fn child_list(&self) -> Option<&UUID> {
+        self.hierarchy_field()?
+            .element_data
+            .child_list_uuid
+            .as_ref()
     }
 
     /// Returns the inner list. You can also use the implied deref.
@@ -61,11 +107,8 @@ impl ListInfo {
 
     /// Returns a list item by id or uuid, or None if it doesn't exist
     pub async fn get_item<A: Into<AllId>>(&'_ self, item_uid: A) -> Result<Rc<Item<'_>>, Error> {
-        let item = crate::get_api()?
-            .get_entry(self.get_id(), item_uid)
-            .await
-            .map(|entry| Item::new(entry, &self.list.name, self.list.id, &self.fields))?;
-        Ok(Rc::new(item))
+        let entry = self.api()?.get_entry(self.get_id(), item_uid).await?;
+        Ok(self.new_item(entry))
     }
 
     /// Returns field (definition) given its name, id, or uuid
@@ -82,25 +125,87 @@ impl ListInfo {
             })
     }
 
+    /// Returns the description of field fname (name, id, or uuid), for use as help text.
+    /// Returns an empty string if the field has no description.
+    pub fn field_help(&self, fname: &str) -> Result<&str, Error> {
+        Ok(self.get_field(fname)?.get_description())
+    }
+
+    /// Returns true if fields differ from this list's fields, in uuid, category,
+    /// or whether multiple values are allowed. Other changes, such as field names, are ignored.
+    pub(crate) fn schema_differs(&self, fields: &[Field]) -> bool {
+        let same = |a: &Field, b: &Field| {
+            a.uuid == b.uuid
+                && a.element_category == b.element_category
+                && a.element_data.multiple == b.element_data.multiple
+        };
+        fields.len() != self.fields.len()
+            || !fields
+                .iter()
+                .all(|f| self.fields.iter().any(|cached| same(f, cached)))
+    }
+
     /// Returns vec of fields
     pub fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
 
-    /// fetch all items of the list, unsorted
+    /// Returns choice (label) names for category ids of the field, for example,
+    /// to render activities whose value_*_as_strings arrays are empty.
+    /// Each result is None if the id is not a choice of the field, or the field was not found.
+    pub fn category_names_for_ids(&self, field_uuid: &str, ids: &[ID]) -> Vec<Option<&str>> {
+        let categories = self
+            .fields
+            .iter()
+            .find(|f| f.uuid == field_uuid)
+            .and_then(|f| f.element_data.predefined_categories.as_ref());
+        ids.iter()
+            .map(|id| {
+                categories
+                    .and_then(|cats| cats.iter().find(|c| c.id == *id))
+                    .map(|c| c.name.as_str())
+            })
+            .collect()
+    }
+
+    /// Returns fields that are visible in public lists
+    pub fn public_fields(&self) -> Vec<&Field> {
+        self.fields
+            .iter()
+            .filter(|f| f.is_visible_in_public_list())
+            .collect()
+    }
+
+    /// fetch all items of the list, unsorted, using the page size from
+    /// ApiConfig.default_query (500 items per request, if not configured).
     pub async fn get_items(&'_ self) -> Result<Vec<Rc<Item<'_>>>, Error> {
-        let max_items = 500usize;
+        let page_size = self.api()?.query_defaults().page_size;
+        self.get_items_with_page_size(page_size).await
+    }
+
+    /// fetch all items of the list, unsorted, using page_size items per request.
+    /// Smaller pages may help avoid timeouts for lists with many fields.
+    /// page_size is clamped to the range 1..=1000.
+    /// Deprecated items are included if configured in ApiConfig.default_query.
+    pub async fn get_items_with_page_size(
+        &'_ self,
+        page_size: usize,
+    ) -> Result<Vec<Rc<Item<'_>>>, Error> {
+        let max_items = page_size.clamp(1, MAX_PAGE_SIZE);
         let mut start_index = 0usize;
         let mut items: Vec<Rc<Item<'_>>> = Vec::new();
+        let api = self.api()?;
+        let allow_deprecated = api.query_defaults().allow_deprecated;
 
         loop {
             // get the items and build the index
-            let entries: Vec<Entry> = crate::get_api()?
+            let entries: Vec<Entry> = api
                 .get_list_entries(
                     self.get_uuid(),
                     &GetEntriesRequest {
                         limit: max_items,
                         skip: start_index,
+                        allow_deprecated,
                         ..Default::default()
                     },
                 )
@@ -118,12 +223,367 @@ impl ListInfo {
         Ok(items)
     }
 
-    fn new_item(&self, entry: Entry) -> Rc<Item> {
+    /// Returns a stream of all items of the list, unsorted, using the page size from
+    /// ApiConfig.default_query (500 items per request, if not configured).
+    /// See stream_items_with_page_size.
+    pub fn stream_items(&'_ self) -> impl Stream<Item = Result<Rc<Item<'_>>, Error>> + '_ {
+        let page_size = self
+            .api()
+            .map(|api| api.query_defaults().page_size)
+            .unwrap_or_default();
+        self.stream_items_with_page_size(page_size)
+    }
+
+    /// Returns a stream of all items of the list, unsorted, using page_size items per request.
+    /// Pages are fetched as the stream is consumed, so items can be processed and dropped
+    /// without holding the whole list in memory.
+    /// page_size is clamped to the range 1..=1000.
+    /// The stream ends after an empty page. If a request fails, the error is returned
+    /// as the last item of the stream.
+    /// Deprecated items are included if configured in ApiConfig.default_query.
+    pub fn stream_items_with_page_size(
+        &'_ self,
+        page_size: usize,
+    ) -> impl Stream<Item = Result<Rc<Item<'_>>, Error>> + '_ {
+        use futures_util::stream::{self, StreamExt};
+
+        let max_items = page_size.clamp(1, MAX_PAGE_SIZE);
+        // state is the number of entries to skip, or None after an error
+        stream::unfold(Some(0usize), move |skip| async move {
+            let skip = skip?;
+            let page: Result<Vec<Entry>, Error> = async {
+                let api = self.api()?;
+                let params = GetEntriesRequest {
+                    limit: max_items,
+                    skip,
+                    allow_deprecated: api.query_defaults().allow_deprecated,
+                    ..Default::default()
+                };
+                api.get_list_entries(self.get_id(), &params).await
+            }
+            .await;
+            match page {
+                Ok(entries) if entries.is_empty() => None,
+                Ok(entries) => {
+                    let next = skip + entries.len();
+                    Some((Ok(entries), Some(next)))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+        .flat_map(move |page| {
+            let items: Vec<Result<Rc<Item<'_>>, Error>> = match page {
+                Ok(entries) => entries
+                    .into_iter()
+                    .map(|entry| Ok(self.new_item(entry)))
+                    .collect(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(items)
+        })
+    }
+
+    /// fetch all items of the list, unsorted, fetching up to `concurrency` pages at a time.
+    /// The number of entries is determined first (with a count query), then pages are
+    /// requested concurrently, and assembled in order.
+    /// concurrency is clamped to the range 1..=8, to avoid exceeding rate limits.
+    /// Entries added while pages are being fetched may be omitted.
+    /// Uses the page size and allow_deprecated settings of ApiConfig.default_query.
+    pub async fn get_items_parallel(
+        &'_ self,
+        concurrency: usize,
+    ) -> Result<Vec<Rc<Item<'_>>>, Error> {
+        use futures_util::stream::{self, StreamExt, TryStreamExt};
+
+        let api = self.api()?;
+        let defaults = api.query_defaults();
+        let page_size = defaults.page_size.clamp(1, MAX_PAGE_SIZE);
+        let total = api
+            .get_list_entries_for_view(
+                self.get_id(),
+                &GetEntriesViewRequest {
+                    // only the count is needed
+                    limit: 1,
+                    allow_deprecated: defaults.allow_deprecated,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .count_data
+            .filtered_total as usize;
+        let num_pages = total.div_ceil(page_size);
+        let api = &api;
+        let pages: Vec<Vec<Entry>> = stream::iter(0..num_pages)
+            .map(|page| async move {
+                let params = GetEntriesRequest {
+                    limit: page_size,
+                    skip: page * page_size,
+                    allow_deprecated: defaults.allow_deprecated,
+                    ..Default::default()
+                };
+                api.get_list_entries(self.get_id(), &params).await
+            })
+            .buffered(concurrency.clamp(1, MAX_CONCURRENT_PAGES))
+            .try_collect()
+            .await?;
+        Ok(pages
+            .into_iter()
+            .flatten()
+            .map(|entry| self.new_item(entry))
+            .collect())
+    }
+
+    /// Returns list items matching the query.
+    /// Queries with group_by or task_style use the list view endpoint
+    /// (see ApiClient::get_list_entries_for_view); all others use ApiClient::get_list_entries.
+    /// Settings not specified in the query are taken from ApiConfig.default_query.
+    pub async fn query(&'_ self, q: &Query) -> Result<Vec<Rc<Item<'_>>>, Error> {
+        let api = self.api()?;
+        let defaults = api.query_defaults();
+        let entries = if q.uses_view()? {
+            api.get_list_entries_for_view(self.get_id(), &q.view_request(defaults))
+                .await?
+                .list_entries
+        } else {
+            api.get_list_entries(self.get_id(), &q.entries_request(defaults))
+                .await?
+        };
+        Ok(entries
+            .into_iter()
+            .map(|entry| self.new_item(entry))
+            .collect())
+    }
+
+    /// Returns items of the list view, with the view's count data.
+    /// Group counts (for group_by or task_style requests) are not returned;
+    /// use ApiClient::get_list_entries_for_view for those.
+    pub async fn get_items_for_view(
+        &'_ self,
+        params: &GetEntriesViewRequest,
+    ) -> Result<(Vec<Rc<Item<'_>>>, FilterCountData), Error> {
+        let resp = self
+            .api()?
+            .get_list_entries_for_view(self.get_id(), params)
+            .await?;
+        let items = resp
+            .list_entries
+            .into_iter()
+            .map(|entry| self.new_item(entry))
+            .collect();
+        Ok((items, resp.count_data))
+    }
+
+    /// Computes an aggregate of numeric field fname over the entries matching filter.
+    /// Zenkit does not support server-side aggregation, so all matching entries are fetched
+    /// (in pages of ApiConfig.default_query.page_size) and the aggregate is computed locally.
+    /// For large lists, this is as costly as get_items, so use a filter where possible.
+    /// Entries without a value for the field are ignored (and not counted by Count).
+    /// Returns an error for Avg, Min, or Max if no entries have a value.
+    pub async fn aggregate_number(
+        &self,
+        fname: &str,
+        filter: Value,
+        op: AggregateOp,
+    ) -> Result<f64, Error> {
+        let field = self.get_field(fname)?;
+        if field.element_category != ElementCategoryId::Number {
+            return Err(Error::Other(format!(
+                "Field {} is not a number field",
+                field.name
+            )));
+        }
+        let mut values = Vec::new();
+        self.for_each_entry(&filter, |entry| {
+            if let Some(n) = entry.get_float_value(&field.uuid)? {
+                values.push(n);
+            }
+            Ok(())
+        })
+        .await?;
+        op.apply(&values)
+            .ok_or_else(|| Error::Other(format!("No values for {:?} of field {}", op, field.name)))
+    }
+
+    /// Returns the text value of field fname (name, id, or uuid of a text field)
+    /// for every entry of the list, paired with the entry id.
+    /// Zenkit does not support fetching selected fields, so full entries are fetched
+    /// one page at a time, but only the ids and values are retained.
+    pub async fn collect_field_values(
+        &self,
+        fname: &str,
+    ) -> Result<Vec<(ID, Option<String>)>, Error> {
+        let uuid = &self.get_field(fname)?.uuid;
+        let mut values = Vec::new();
+        self.for_each_entry(&json!({}), |entry| {
+            values.push((entry.id, entry.get_text_value(uuid)?.map(String::from)));
+            Ok(())
+        })
+        .await?;
+        Ok(values)
+    }
+
+    // Fetches all entries matching filter, one page at a time, and calls f for each entry.
+    // Uses the page size and allow_deprecated settings of ApiConfig.default_query.
+    async fn for_each_entry<F>(&self, filter: &Value, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&Entry) -> Result<(), Error>,
+    {
+        let api = self.api()?;
+        let defaults = api.query_defaults();
+        let page_size = defaults.page_size.clamp(1, MAX_PAGE_SIZE);
+        let mut skip = 0usize;
+        loop {
+            let entries = api
+                .get_list_entries(
+                    self.get_id(),
+                    &GetEntriesRequest {
+                        filter: filter.clone(),
+                        limit: page_size,
+                        skip,
+                        allow_deprecated: defaults.allow_deprecated,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            if entries.is_empty() {
+                return Ok(());
+            }
+            skip += entries.len();
+            for entry in entries.iter() {
+                f(entry)?;
+            }
+        }
+    }
+
+    /// fetch all items of the list, indexed by the text value of field fname
+    /// (name, id, or uuid of a text field).
+    /// Items without a value for the field are omitted.
+    /// If more than one item has the same value, the last one fetched is kept.
+    pub async fn get_items_indexed_by(
+        &'_ self,
+        fname: &str,
+    ) -> Result<HashMap<String, Rc<Item<'_>>>, Error> {
+        self.get_field(fname)?;
+        let mut index = HashMap::new();
+        for item in self.get_items().await?.into_iter() {
+            if let Some(key) = item.get_text_value(fname)? {
+                index.insert(key.to_string(), item);
+            }
+        }
+        Ok(index)
+    }
+
+    /// fetch all items of the list, sorted by the key returned by key_fn.
+    /// Use this to sort by values Zenkit can't sort on, such as category names
+    /// or computed values; to sort by a column, use ApiClient::get_list_entries_sorted.
+    /// key_fn is called once per item. Items with equal keys keep the order they were fetched.
+    pub async fn get_items_sorted_by<K, F>(&'_ self, key_fn: F) -> Result<Vec<Rc<Item<'_>>>, Error>
+    where
+        K: Ord,
+        F: Fn(&Item) -> K,
+    {
+        let mut items = self.get_items().await?;
+        items.sort_by_cached_key(|item| key_fn(item));
+        Ok(items)
+    }
+
+    /// Returns (id, title) of up to limit items whose primary field contains query,
+    /// for example, as choices for a reference field. See Item::title.
+    /// Returns an error if the list has no primary field.
+    /// limit is clamped to the range 1..=1000.
+    pub async fn autocomplete_primary(
+        &self,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<(ID, String)>, Error> {
+        let field =
+            self.fields.iter().find(|f| f.is_primary).ok_or_else(|| {
+                Error::Other(format!("List {} has no primary field", self.list.name))
+            })?;
+        let api = self.api()?;
+        let entries = api
+            .get_list_entries(
+                self.get_id(),
+                &GetEntriesRequest {
+                    filter: FilterBuilder::new()
+                        .text(field.id, FilterTermModus::Contains, query)
+                        .build(),
+                    limit: limit.clamp(1, MAX_PAGE_SIZE),
+                    allow_deprecated: api.query_defaults().allow_deprecated,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                let item = self.new_item(entry);
+                (item.get_id(), item.title())
+            })
+            .collect())
+    }
+
+    /// Returns the item anchor_id with up to `before` items preceding it and up to `after`
+    /// items following it, in ascending order of the column sort_field (e.g., "created_at").
+    /// Zenkit has no cursor-based paging, so the anchor's position is found by fetching
+    /// entries in sort order (in pages of ApiConfig.default_query.page_size) until the anchor
+    /// is found; then the window around it is fetched with a single request.
+    /// Returns an error if the anchor is not in the list.
+    pub async fn get_items_around(
+        &'_ self,
+        anchor_id: ID,
+        before: usize,
+        after: usize,
+        sort_field: &str,
+    ) -> Result<Vec<Rc<Item<'_>>>, Error> {
+        let api = self.api()?;
+        let defaults = api.query_defaults();
+        let page_size = defaults.page_size.clamp(1, MAX_PAGE_SIZE);
+        let request = |skip: usize, limit: usize| GetEntriesRequest {
+            limit,
+            skip,
+            allow_deprecated: defaults.allow_deprecated,
+            order_by: vec![OrderBy {
+                column: Some(sort_field.to_string()),
+                direction: SortDirection::Asc,
+            }],
+            ..Default::default()
+        };
+        let mut skip = 0usize;
+        let position = loop {
+            let entries = api
+                .get_list_entries(self.get_id(), &request(skip, page_size))
+                .await?;
+            if entries.is_empty() {
+                return Err(Error::Other(format!(
+                    "Item {} not found in list {}",
+                    anchor_id, self.list.name
+                )));
+            }
+            if let Some(i) = entries.iter().position(|e| e.id == anchor_id) {
+                break skip + i;
+            }
+            skip += entries.len();
+        };
+        let start = position.saturating_sub(before);
+        let entries = api
+            .get_list_entries(self.get_id(), &request(start, position - start + 1 + after))
+            .await?;
+        Ok(entries
+            .into_iter()
+            .map(|entry| self.new_item(entry))
+            .collect())
+    }
+
+    pub(crate) fn new_item(&self, entry: Entry) -> Rc<Item<'_>> {
         Rc::new(Item::new(
             entry,
             &self.list.name,
             self.list.id,
+            self.list.workspace_id,
             &self.fields,
+            self.task_element(),
+            &self.api,
         ))
     }
 
@@ -132,21 +592,26 @@ impl ListInfo {
     /// For values, you can use the fset_* helper functions (UpdateAction not required for new
     /// items)
     /// Returned item has additional fields filled in by system (id, uuid, created_at, etc.)
+    /// Returns an error if the returned entry has no id.
     pub async fn create_item(&'_ self, values: Vec<FieldSetVal>) -> Result<Rc<Item<'_>>, Error> {
-        let mut map = JsonMap::new();
-
-        for f_set in values.into_iter() {
-            self.generic_set(&mut map, f_set).await?;
+        let payload = self.build_update_payload(values).await?;
+        let entry = self.api()?.create_entry(self.get_id(), payload).await?;
+        let item = self.new_item(entry);
+        if !item.is_persisted() {
+            return Err(Error::Other(format!(
+                "Create item in list {} returned an entry without id",
+                self.list.name
+            )));
         }
-        let entry = crate::get_api()?
-            .create_entry(self.get_id(), Value::Object(map))
-            .await?;
-        Ok(self.new_item(entry))
+        Ok(item)
     }
 
     /// Updates an item with one or more field changes
     /// For values, you should use the fup_* (rather than fset_*) helper functions to ensure UpdateAction
     /// is set correctly.
+    /// Zenkit applies a single update action to all multi-value fields (persons, categories,
+    /// and references) of an update, so all of these in one call must use the same action.
+    /// An error is returned, before sending the update, if actions are mixed.
     /// List and fields are 'Any' type: name, id, or uuid.
     /// For Person field, value can be name.
     /// For choice field, value can be choice (category) name.
@@ -156,16 +621,243 @@ impl ListInfo {
         item_id: ID,
         values: Vec<FieldSetVal>,
     ) -> Result<Rc<Item<'_>>, Error> {
+        let payload = self.build_update_payload(values).await?;
+        let entry = self
+            .api()?
+            .update_entry(self.get_id(), item_id, payload)
+            .await?;
+        Ok(self.new_item(entry))
+    }
+
+    /// Splits items of a task list into (done, todo), using the task element
+    /// (see Item::is_done), without querying the server.
+    /// If this is not a task list, all items are returned as todo.
+    pub fn split_done<'li>(
+        &self,
+        items: &[Rc<Item<'li>>],
+    ) -> (Vec<Rc<Item<'li>>>, Vec<Rc<Item<'li>>>) {
+        items
+            .iter()
+            .cloned()
+            .partition(|item| item.is_done() == Some(true))
+    }
+
+    /// Marks a task done or not done, by setting the list's task element (see task_element).
+    /// For a checkbox task element, the box is checked or unchecked. For a categories
+    /// task element, done selects the "Done" choice, and not done selects the "To Do"
+    /// choice (see Element::todo_category), or clears the field if there is no such choice.
+    /// To select another choice, such as "Blocked", use update_item.
+    /// Returns an error if the list is not a task list.
+    pub async fn set_item_done(&'_ self, item_id: ID, done: bool) -> Result<Rc<Item<'_>>, Error> {
+        let payload = self.task_done_payload(done)?;
+        let entry = self
+            .api()?
+            .update_entry(self.get_id(), item_id, payload)
+            .await?;
+        Ok(self.new_item(entry))
+    }
+
+    fn task_done_payload(&self, done: bool) -> Result<Value, Error> {
+        let field = self
+            .task_element()
+            .ok_or_else(|| Error::Other(format!("List {} is not a task list", self.list.name)))?;
         let mut map = JsonMap::new();
-        for f_set in values.into_iter() {
-            self.generic_set(&mut map, f_set).await?;
+        match field.element_category {
+            ElementCategoryId::Checkbox => {
+                map.insert(format!("{}_{}", field.uuid, "checked"), json!(done));
+            }
+            ElementCategoryId::Categories => {
+                let done_category = field.done_category().ok_or_else(|| {
+                    Error::Other(format!("Task field {} has no 'Done' choice", field.name))
+                })?;
+                let choice = if done {
+                    Some(done_category)
+                } else {
+                    field.todo_category()
+                };
+                let ids: Vec<ID> = choice.map(|c| c.id).into_iter().collect();
+                map.insert(format!("{}_{}", field.uuid, "categories"), json!(ids));
+            }
+            typ => {
+                return Err(Error::Other(format!(
+                    "Unsupported task field {} (type {:?})",
+                    field.name, typ
+                )));
+            }
         }
-        let entry = crate::get_api()?
-            .update_entry(self.get_id(), item_id, Value::Object(map))
+        Ok(Value::Object(map))
+    }
+
+    /// Sets a reference field to the item in another list whose lookup field has lookup_value,
+    /// for example, an order number. target_list is the name, id, or uuid of a list
+    /// in the same workspace, and lookup_field must be a text or number field of that list.
+    /// Returns an error if no item, or more than one item, matches.
+    /// Matching items are found with a filter query, so only they are fetched.
+    /// Returns updated object
+    pub async fn set_reference_by_lookup(
+        &'_ self,
+        item_id: ID,
+        fname: &str,
+        target_list: &str,
+        lookup_field: &str,
+        lookup_value: &str,
+    ) -> Result<Rc<Item<'_>>, Error> {
+        let api = self.api()?;
+        let target = api
+            .get_list_info(self.list.workspace_id, target_list)
+            .await?;
+        let field = target.get_field(lookup_field)?;
+        let filter = match field.element_category {
+            ElementCategoryId::Text => {
+                FilterBuilder::new().text(field.id, FilterTermModus::Equals, lookup_value)
+            }
+            ElementCategoryId::Number => {
+                let n = lookup_value.parse::<f64>().map_err(|_| {
+                    Error::Other(format!(
+                        "Invalid number '{}' for field {}",
+                        lookup_value, lookup_field
+                    ))
+                })?;
+                FilterBuilder::new().number_range(field.id, n, n)
+            }
+            typ => {
+                return Err(Error::Other(format!(
+                    "Lookup field {} must be a text or number field (type {:?})",
+                    lookup_field, typ
+                )))
+            }
+        };
+        let entries = api
+            .get_list_entries(
+                target.get_id(),
+                &GetEntriesRequest {
+                    filter: filter.build(),
+                    limit: MAX_PAGE_SIZE,
+                    allow_deprecated: api.query_defaults().allow_deprecated,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        // the server's text comparison may ignore case, so values are compared exactly
+        let items: Vec<_> = entries
+            .into_iter()
+            .map(|entry| target.new_item(entry))
+            .collect();
+        let mut found = items.iter().filter(|item| match field.element_category {
+            ElementCategoryId::Text => {
+                matches!(item.get_text_value(lookup_field), Ok(Some(v)) if v == lookup_value)
+            }
+            _ => matches!(
+                (item.get_float_value(lookup_field), lookup_value.parse::<f64>()),
+                (Ok(Some(v)), Ok(n)) if v == n
+            ),
+        });
+        let uuid = match (found.next(), found.next()) {
+            (Some(item), None) => item.get_uuid().clone(),
+            (None, _) => {
+                return Err(Error::Other(format!(
+                    "No item in list {} with {}='{}'",
+                    target.name, lookup_field, lookup_value
+                )))
+            }
+            (Some(_), Some(_)) => {
+                return Err(Error::Other(format!(
+                    "More than one item in list {} with {}='{}'",
+                    target.name, lookup_field, lookup_value
+                )))
+            }
+        };
+        self.update_item(item_id, vec![fup_s(fname, &uuid, UpdateAction::Replace)])
+            .await
+    }
+
+    /// Moves the item to the top of the list, by setting its sort order
+    /// below the lowest sort order in the list.
+    /// Returns updated object
+    pub async fn move_item_to_top(&'_ self, item_id: ID) -> Result<Rc<Item<'_>>, Error> {
+        self.move_item(item_id, SortDirection::Asc).await
+    }
+
+    /// Moves the item to the bottom of the list, by setting its sort order
+    /// above the highest sort order in the list.
+    /// Returns updated object
+    pub async fn move_item_to_bottom(&'_ self, item_id: ID) -> Result<Rc<Item<'_>>, Error> {
+        self.move_item(item_id, SortDirection::Desc).await
+    }
+
+    /// Moves item to the first position when sorted in the direction:
+    /// Asc for top, Desc for bottom.
+    async fn move_item(&'_ self, item_id: ID, dir: SortDirection) -> Result<Rc<Item<'_>>, Error> {
+        let api = self.api()?;
+        let first = api
+            .get_list_entries_sorted(self.get_id(), Some(("sortOrder", dir)), 1, 0)
+            .await?
+            .pop()
+            .ok_or_else(|| Error::Other(format!("Item {} not found", item_id)))?;
+        if first.id == item_id {
+            return Ok(self.new_item(first));
+        }
+        let sort_order = match dir {
+            SortDirection::Asc => first.sort_order - 1.0,
+            SortDirection::Desc => first.sort_order + 1.0,
+        };
+        let entry = api
+            .update_entry(self.get_id(), item_id, json!({ "sortOrder": sort_order }))
             .await?;
         Ok(self.new_item(entry))
     }
 
+    /// Returns the json object sent to the server by create_item and update_item.
+    /// Values are validated and converted as described in update_item.
+    /// All values are validated (see validate_values) before the payload is built,
+    /// and the first error found is returned.
+    /// No network call is made, unless a person field is set by name or uuid,
+    /// which may require loading the workspace user list.
+    pub async fn build_update_payload(&self, values: Vec<FieldSetVal>) -> Result<Value, Error> {
+        // check all values, including choice names, before looking up persons
+        // or building the payload
+        if let Err(errors) = self.validate_values(&values) {
+            if let Some(e) = errors.into_iter().next() {
+                return Err(e);
+            }
+        }
+        let mut map = JsonMap::new();
+        for f_set in values.into_iter() {
+            self.generic_set(&mut map, f_set).await?;
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Checks values for errors, without making any network calls, and returns
+    /// all problems found rather than stopping at the first.
+    /// Checks include: field names, compatibility of value type and update action with the
+    /// field type, numeric conversions, choice names, uuid syntax,
+    /// and multiple values for single-value fields.
+    /// Person names are not looked up (that happens in update_item or create_item).
+    pub fn validate_values(&self, values: &[FieldSetVal]) -> Result<(), Vec<Error>> {
+        use FieldVal::{ArrID, ArrStr, Str};
+
+        let mut errors = Vec::new();
+        let mut scratch = JsonMap::new();
+        for (fname, val, act) in values.iter() {
+            let category = self.get_field(fname).map(|f| f.element_category);
+            // person names are resolved to ids later; substitute placeholder ids
+            let val = match (category, val) {
+                (Ok(ElementCategoryId::Persons), Str(_)) => ArrID(vec![0]),
+                (Ok(ElementCategoryId::Persons), ArrStr(names)) => ArrID(vec![0; names.len()]),
+                (_, v) => v.clone(),
+            };
+            if let Err(e) = self.set_value(&mut scratch, (fname.clone(), val, *act)) {
+                errors.push(e);
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
     /// Add field settings to object map.
     /// For choice field, value can be choice (category) name.
     /// For Person field, value can be name.
@@ -173,7 +865,53 @@ impl ListInfo {
     /// you must have previously made a one-time call to init_user_cache(),
     /// or this method will return Error::NotInitialized.
     async fn generic_set(&self, obj: &mut JsonMap, field_val: FieldSetVal) -> Result<(), Error> {
-        use FieldVal::{ArrID, ArrStr, Float, Formatted, Int, Str};
+        let field_val = self.resolve_persons(field_val).await?;
+        self.set_value(obj, field_val)
+    }
+
+    /// For Person fields, converts names to user ids (ArrID).
+    /// All other values are returned unchanged.
+    async fn resolve_persons(&self, field_val: FieldSetVal) -> Result<FieldSetVal, Error> {
+        use FieldVal::{ArrID, ArrStr, Str};
+
+        let (fname, val, act) = field_val;
+        let field = self.get_field(&fname)?;
+        if field.element_category != ElementCategoryId::Persons {
+            return Ok((fname, val, act));
+        }
+        let names = match val {
+            Str(s) => vec![s],
+            ArrStr(pvec) => {
+                if !field.element_data.multiple && pvec.len() > 1 {
+                    return Err(Error::Other(format!(
+                        "Field {} can't accept more than one person but {} were provided",
+                        field.name,
+                        pvec.len()
+                    )));
+                }
+                pvec
+            }
+            _ => return Ok((fname, val, act)),
+        };
+        let api = self.api()?;
+        let mut v = Vec::<ID>::new();
+        for pname in names.iter() {
+            v.push(
+                match api.get_user_id(self.list.workspace_id, pname).await? {
+                    Some(uid) => uid,
+                    None => {
+                        return Err(Error::Other(format!("User not found: '{}'", pname)));
+                    }
+                },
+            );
+        }
+        Ok((fname, ArrID(v), act))
+    }
+
+    /// Add field settings to object map. Person names must have been
+    /// converted to ids (see resolve_persons).
+    fn set_value(&self, obj: &mut JsonMap, field_val: FieldSetVal) -> Result<(), Error> {
+        use FieldVal::{ArrID, ArrStr, Bool, DateRange, DateTime, Float, Formatted, Int, Str};
         use UpdateAction::{Null, Replace};
 
         let field = self.get_field(&field_val.0)?;
@@ -236,6 +974,10 @@ impl ListInfo {
                 };
                 obj.insert(format!("{}_{}", field.uuid, "number"), Value::Number(num));
             }
+            (ElementCategoryId::Checkbox, Bool(b), Replace)
+            | (ElementCategoryId::Checkbox, Bool(b), Null) => {
+                obj.insert(format!("{}_{}", field.uuid, "checked"), Value::Bool(b));
+            }
             (ElementCategoryId::URL, Str(s), Replace) | (ElementCategoryId::URL, Str(s), Null) => {
                 obj.insert(format!("{}_{}", field.uuid, "link"), Value::String(s));
             }
@@ -243,44 +985,48 @@ impl ListInfo {
             | (ElementCategoryId::Date, Str(s), Null) => {
                 obj.insert(format!("{}_{}", field.uuid, "date"), Value::String(s));
             }
-            (ElementCategoryId::Persons, Str(s), act) => {
-                let api = crate::get_api()?;
-                match api.get_user_id(self.list.workspace_id, &s).await? {
-                    Some(uid) => {
-                        obj.insert(format!("{}_{}", field.uuid, "persons"), json!(vec![uid]));
-                    }
-                    None => {
-                        return Err(Error::Other(format!("User not found: '{}'", s)));
-                    }
-                }
-                if act != Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
-                }
+            (ElementCategoryId::Date, DateTime(dt), Replace)
+            | (ElementCategoryId::Date, DateTime(dt), Null) => {
+                obj.insert(
+                    format!("{}_{}", field.uuid, "date"),
+                    Value::String(dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+                );
+                obj.insert(format!("{}_{}", field.uuid, "hasTime"), Value::Bool(true));
             }
-            (ElementCategoryId::Persons, ArrStr(pvec), act) => {
-                if !field.element_data.multiple && pvec.len() > 1 {
+            (
+                ElementCategoryId::Date,
+                DateRange {
+                    start,
+                    end,
+                    has_time,
+                },
+                Replace,
+            )
+            | (
+                ElementCategoryId::Date,
+                DateRange {
+                    start,
+                    end,
+                    has_time,
+                },
+                Null,
+            ) => {
+                if *end < *start {
                     return Err(Error::Other(format!(
-                        "Field {} can't accept more than one person but {} were provided",
-                        field.name,
-                        pvec.len()
+                        "End date {} is before start date {} for field {}",
+                        end, start, field.name
                     )));
                 }
-                let api = crate::get_api()?;
-                let mut v = Vec::<ID>::new();
-                for pname in pvec.iter() {
-                    v.push(
-                        match api.get_user_id(self.list.workspace_id, &pname).await? {
-                            Some(uid) => uid,
-                            None => {
-                                return Err(Error::Other(format!("User not found: '{}'", pname)));
-                            }
-                        },
-                    );
-                }
-                obj.insert(format!("{}_{}", field.uuid, "persons"), json!(v));
-                if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
-                }
+                let format = |dt: &chrono::DateTime<Utc>| {
+                    if has_time {
+                        dt.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+                    } else {
+                        dt.format("%Y-%m-%d").to_string()
+                    }
+                };
+                obj.insert(format!("{}_{}", field.uuid, "date"), json!(format(&start)));
+                obj.insert(format!("{}_{}", field.uuid, "endDate"), json!(format(&end)));
+                obj.insert(format!("{}_{}", field.uuid, "hasTime"), json!(has_time));
             }
             (ElementCategoryId::Persons, Int(pid), act) => {
                 obj.insert(
@@ -288,7 +1034,7 @@ impl ListInfo {
                     json!(vec![pid as u64]),
                 );
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::Persons, ArrID(pvec), act) => {
@@ -301,7 +1047,7 @@ impl ListInfo {
                 }
                 obj.insert(format!("{}_{}", field.uuid, "persons"), json!(pvec));
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::Categories, Str(s), act) => {
@@ -310,7 +1056,7 @@ impl ListInfo {
                     json!(vec![field.get_choice_id(&s)?]),
                 );
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::Categories, Int(cid), act) => {
@@ -319,7 +1065,7 @@ impl ListInfo {
                     json!(vec![cid as u64]),
                 );
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::Categories, ArrID(cvec), act) => {
@@ -332,7 +1078,7 @@ impl ListInfo {
                 }
                 obj.insert(format!("{}_{}", field.uuid, "categories"), json!(cvec));
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::Categories, ArrStr(cvec), act) => {
@@ -351,14 +1097,14 @@ impl ListInfo {
                         .collect::<Result<Vec<ID>, Error>>()?),
                 );
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::References, Str(s), act) => {
                 check_uuid(&s, &field.name)?;
                 obj.insert(format!("{}_{}", field.uuid, "references"), json!(vec![s]));
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::References, Int(rid), act) => {
@@ -367,7 +1113,7 @@ impl ListInfo {
                     json!(vec![rid as u64]),
                 );
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (ElementCategoryId::References, ArrStr(rvec), act) => {
@@ -383,7 +1129,7 @@ impl ListInfo {
                 }
                 obj.insert(format!("{}_{}", field.uuid, "references"), json!(rvec));
                 if act != UpdateAction::Null {
-                    obj.insert(String::from("updateAction"), Value::String(act.to_string()));
+                    set_update_action(obj, act)?;
                 }
             }
             (typ, value, action) => {
@@ -396,15 +1142,22 @@ impl ListInfo {
         Ok(())
     }
 
-    /// Adds comment to the item
+    /// Adds comment to the item.
+    /// Returns an error, without posting the comment, if the user's role for the list
+    /// is read-only. If the role could not be determined (see ApiClient::can_comment),
+    /// the comment is posted, and Zenkit returns an error if it is not allowed.
     pub async fn add_item_comment<A: Into<AllId>>(
         &self,
         item_allid: A,
         message: String,
     ) -> Result<(), Error> {
+        let api = self.api()?;
+        if api.can_comment(self.list.id).await? == Some(false) {
+            return Err(Error::Other("insufficient role to comment".to_string()));
+        }
         let item = self.get_item(item_allid).await?;
         let comment = crate::types::NewComment { message };
-        let _activity = crate::get_api()?
+        let _activity = api
             .create_entry_comment(self.list.id, item.as_entry().id, &comment)
             .await?;
         Ok(())
@@ -413,7 +1166,8 @@ impl ListInfo {
     /// Adds comment to the list
     pub async fn add_list_comment(&self, message: String) -> Result<(), Error> {
         let comment = crate::types::NewComment { message };
-        let _ = crate::get_api()?
+        let _ = self
+            .api()?
             .create_list_comment(self.list.id, &comment)
             .await?;
         Ok(())
@@ -512,6 +1266,58 @@ pub fn fup_f(fname: &str, val: f64, act: UpdateAction) -> FieldSetVal {
     (fname.to_string(), FieldVal::Float(val), act)
 }
 
+/// Set checkbox field
+#[inline]
+pub fn fset_bool(fname: &str, val: bool) -> FieldSetVal {
+    fup_bool(fname, val, UpdateAction::Null)
+}
+
+/// Update checkbox field
+#[inline]
+pub fn fup_bool(fname: &str, val: bool, act: UpdateAction) -> FieldSetVal {
+    (fname.to_string(), FieldVal::Bool(val), act)
+}
+
+/// Set date field to a date without time, formatted as YYYY-MM-DD
+#[inline]
+pub fn fset_date(fname: &str, date: NaiveDate) -> FieldSetVal {
+    fset_s(fname, &date.format("%Y-%m-%d").to_string())
+}
+
+/// Set date field to a date and time, formatted as ISO 8601 in UTC
+/// (e.g., "2021-03-01T14:30:00.000Z")
+#[inline]
+pub fn fset_datetime(fname: &str, val: DateTime<Utc>) -> FieldSetVal {
+    fup_datetime(fname, val, UpdateAction::Null)
+}
+
+/// Update date field with a date and time
+#[inline]
+pub fn fup_datetime(fname: &str, val: DateTime<Utc>, act: UpdateAction) -> FieldSetVal {
+    (fname.to_string(), FieldVal::DateTime(val), act)
+}
+
+/// Set date field to a range from start to end. If has_time is false,
+/// the dates are formatted as YYYY-MM-DD and times are ignored.
+/// Setting the field returns an error if end is before start.
+#[inline]
+pub fn fset_date_range(
+    fname: &str,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    has_time: bool,
+) -> FieldSetVal {
+    (
+        fname.to_string(),
+        FieldVal::DateRange {
+            start,
+            end,
+            has_time,
+        },
+        UpdateAction::Null,
+    )
+}
+
 /// Set field to Vec of IDs
 #[inline]
 pub fn fset_vid(fname: &str, val: Vec<ID>) -> FieldSetVal {
@@ -536,8 +1342,54 @@ pub fn fup_vs(fname: &str, val: Vec<String>, act: UpdateAction) -> FieldSetVal {
     (fname.to_string(), FieldVal::ArrStr(val), act)
 }
 
+/// Sets the update action of the payload. The action applies to all multi-value fields
+/// in the payload, so an error is returned if a different action was already set.
+fn set_update_action(obj: &mut JsonMap, act: UpdateAction) -> Result<(), Error> {
+    let act = act.to_string();
+    match obj.get("updateAction").and_then(|v| v.as_str()) {
+        Some(prev) if prev != act => Err(Error::Other(format!(
+            "Conflicting update actions ({} and {}): use a separate update for each action",
+            prev, act
+        ))),
+        _ => {
+            obj.insert(String::from("updateAction"), Value::String(act));
+            Ok(())
+        }
+    }
+}
+
+/// Aggregate operation for ListInfo::aggregate_number
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggregateOp {
+    /// sum of values
+    Sum,
+    /// mean of values
+    Avg,
+    /// smallest value
+    Min,
+    /// largest value
+    Max,
+    /// number of values
+    Count,
+}
+
+impl AggregateOp {
+    /// Returns the aggregate of the values, or None if there are no values to average,
+    /// or take the min or max of
+    fn apply(self, values: &[f64]) -> Option<f64> {
+        match self {
+            AggregateOp::Sum => Some(values.iter().sum()),
+            AggregateOp::Count => Some(values.len() as f64),
+            AggregateOp::Avg if values.is_empty() => None,
+            AggregateOp::Avg => Some(values.iter().sum::<f64>() / values.len() as f64),
+            AggregateOp::Min => values.iter().cloned().reduce(f64::min),
+            AggregateOp::Max => values.iter().cloned().reduce(f64::max),
+        }
+    }
+}
+
 /// Hold value of field for set and update operations
-#[derive(Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum FieldVal {
     /// String value - for text and other string fields
     Str(String),
@@ -551,18 +1403,395 @@ pub enum FieldVal {
     Int(i64),
     /// Float numeric field
     Float(f64),
+    /// Checkbox field
+    Bool(bool),
+    /// Date field, with time
+    DateTime(DateTime<Utc>),
+    /// Date field with start and end. If has_time is false, only the dates are used.
+    DateRange {
+        /// start date
+        start: DateTime<Utc>,
+        /// end date, which must not be before start
+        end: DateTime<Utc>,
+        /// whether the times of start and end are used
+        has_time: bool,
+    },
 }
 
 impl fmt::Display for FieldVal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use FieldVal::{ArrID, ArrStr, Float, Formatted, Int, Str};
+        use FieldVal::{ArrID, ArrStr, Bool, DateRange, DateTime, Float, Formatted, Int, Str};
         match self {
             Str(s) => write!(f, "{}", s),
             Int(n) => write!(f, "{}", n),
             Float(n) => write!(f, "{}", n),
+            Bool(b) => write!(f, "{}", b),
+            DateTime(dt) => write!(f, "{}", dt),
+            DateRange { start, end, .. } => write!(f, "({},{})", start, end),
             Formatted(s, fmt) => write!(f, "({},{})", s, fmt.to_string()),
             ArrStr(arr) => write!(f, "{:?}", arr),
             ArrID(arr) => write!(f, "{:?}", arr),
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{
+        fset_bool, fset_date, fset_date_range, fset_datetime, fset_i, fset_s, fup_vid, AggregateOp,
+        ListInfo,
+    };
+    use crate::types::UpdateAction;
+    use crate::types::{DateTime, Element, Entry, List, NaiveDate, Utc};
+    use serde_json::{json, Value};
+    use std::sync::Weak;
+
+    /// Constructs a field definition for tests
+    pub(crate) fn test_field(id: u64, name: &str, category: u8, element_data: Value) -> Element {
+        serde_json::from_value(json!({
+            "id": id,
+            "shortId": format!("f{}", id),
+            "uuid": format!("00000000-0000-0000-0000-{:012}", id),
+            "name": name,
+            "businessData": {},
+            "elementData": element_data,
+            "isPrimary": id == 1,
+            "isAutoCreated": false,
+            "sortOrder": id,
+            "visible": true,
+            "created_at": "2021-01-01T00:00:00Z",
+            "updated_at": "2021-01-01T00:00:00Z",
+            "elementcategory": category,
+            "listId": 100,
+        }))
+        .unwrap()
+    }
+
+    /// Constructs a list with a text field "Name", an integer field "Count",
+    /// and a date field "Due"
+    pub(crate) fn test_list_info() -> ListInfo {
+        test_list_info_with(Vec::new())
+    }
+
+    /// Constructs a task list, with the fields of test_list_info and a checkbox field "Done"
+    /// as task element
+    pub(crate) fn test_task_list_info() -> ListInfo {
+        let mut li = test_list_info_with(vec![test_field(4, "Done", 5, json!({}))]);
+        li.list.settings = serde_json::from_value(json!({ "tasks": { "elementId": 4 } })).unwrap();
+        li
+    }
+
+    /// Constructs a list with the fields of test_list_info, followed by `extra_fields`
+    pub(crate) fn test_list_info_with(extra_fields: Vec<Element>) -> ListInfo {
+        let list: List = serde_json::from_value(json!({
+            "id": 100,
+            "shortId": "l100",
+            "uuid": "00000000-0000-0000-0000-000000000100",
+            "name": "Tasks",
+            "isBuilding": false,
+            "isMigrating": false,
+            "sortOrder": 1,
+            "description": "",
+            "defaultViewModus": 0,
+            "created_at": "2021-01-01T00:00:00Z",
+            "updated_at": "2021-01-01T00:00:00Z",
+            "workspaceId": 10,
+            "visibility": 0,
+            "created_by": 1,
+        }))
+        .unwrap();
+        let fields = vec![
+            test_field(1, "Name", 1, json!({})),
+            test_field(2, "Count", 2, json!({ "format": { "name": "integer" } })),
+            test_field(3, "Due", 4, json!({})),
+        ];
+        let fields = fields.into_iter().chain(extra_fields).collect();
+        ListInfo::new(list, fields, Weak::new())
+    }
+
+    /// Constructs an entry of the test list, with the field values in `values`
+    /// (keyed by "{uuid}_{suffix}", as received from Zenkit)
+    pub(crate) fn test_entry(values: Value) -> Entry {
+        let mut entry = json!({
+            "id": 1,
+            "shortId": "e1",
+            "uuid": "00000000-0000-0000-0000-100000000001",
+            "listId": 100,
+            "created_at": "2021-01-01T00:00:00Z",
+            "updated_at": "2021-01-01T00:00:00Z",
+            "displayString": "one",
+            "sortOrder": 1,
+            "created_by": 1,
+            "updated_by": 1,
+            "comment_count": 0,
+            "checklists": [],
+        });
+        if let (Some(entry), Some(values)) = (entry.as_object_mut(), values.as_object()) {
+            entry.extend(values.clone());
+        }
+        serde_json::from_value(entry).unwrap()
+    }
+
+    /// Constructs the json of a choice of a categories field
+    pub(crate) fn test_category(id: u64, name: &str, element_id: u64) -> Value {
+        json!({
+            "id": id,
+            "shortId": format!("c{}", id),
+            "uuid": format!("00000000-0000-0000-0000-1000000000{:02}", id),
+            "name": name,
+            "colorHex": "#00ff00",
+            "created_at": "2021-01-01T00:00:00Z",
+            "updated_at": "2021-01-01T00:00:00Z",
+            "elementId": element_id,
+            "listId": 100,
+            "resourceTags": [],
+            "sortOrder": id,
+        })
+    }
+
+    /// Constructs a categories field, whose choices have ids 1, 2, ...
+    pub(crate) fn test_categories_field(id: u64, name: &str, choices: &[&str]) -> Element {
+        let categories: Vec<Value> = choices
+            .iter()
+            .zip(1..)
+            .map(|(choice, choice_id)| test_category(choice_id, choice, id))
+            .collect();
+        test_field(id, name, 6, json!({ "predefinedCategories": categories }))
+    }
+
+    /// Constructs the json of a file attached to a files field
+    pub(crate) fn test_file(id: u64, element_id: u64, file_name: &str, url: &str) -> Value {
+        json!({
+            "id": id,
+            "shortId": format!("f{}", id),
+            "uuid": format!("00000000-0000-0000-0000-2000000000{:02}", id),
+            "fileName": file_name,
+            "size": null,
+            "mimetype": "application/pdf",
+            "isImage": null,
+            "s3key": null,
+            "fileUrl": url,
+            "created_at": "2021-01-01T00:00:00Z",
+            "updated_at": "2021-01-01T00:00:00Z",
+            "deprecated_at": null,
+            "uploaderId": 1,
+            "listId": 100,
+            "elementId": element_id,
+            "importError": null,
+            "provider": null,
+            "metadata": null,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_build_update_payload() {
+        let li = test_list_info();
+        let payload = li
+            .build_update_payload(vec![fset_s("Name", "hello"), fset_i("Count", 5)])
+            .await
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "00000000-0000-0000-0000-000000000001_text": "hello",
+                "00000000-0000-0000-0000-000000000002_number": 5,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fset_date() {
+        let li = test_list_info();
+        let due = NaiveDate::from_ymd_opt(2021, 3, 1).unwrap();
+        let payload = li
+            .build_update_payload(vec![fset_date("Due", due)])
+            .await
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({ "00000000-0000-0000-0000-000000000003_date": "2021-03-01" })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fset_datetime() {
+        let li = test_list_info();
+        let due = "2021-03-01T14:30:00Z".parse().unwrap();
+        let payload = li
+            .build_update_payload(vec![fset_datetime("Due", due)])
+            .await
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "00000000-0000-0000-0000-000000000003_date": "2021-03-01T14:30:00.000Z",
+                "00000000-0000-0000-0000-000000000003_hasTime": true,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fset_date_range() {
+        let li = test_list_info();
+        let start = "2021-03-01T09:00:00Z".parse().unwrap();
+        let end: DateTime<Utc> = "2021-03-05T17:00:00Z".parse().unwrap();
+        let payload = li
+            .build_update_payload(vec![fset_date_range("Due", start, end.clone(), false)])
+            .await
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({
+                "00000000-0000-0000-0000-000000000003_date": "2021-03-01",
+                "00000000-0000-0000-0000-000000000003_endDate": "2021-03-05",
+                "00000000-0000-0000-0000-000000000003_hasTime": false,
+            })
+        );
+        let start = "2021-03-06T00:00:00Z".parse().unwrap();
+        assert!(li
+            .validate_values(&[fset_date_range("Due", start, end, true)])
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fset_bool() {
+        let li = test_task_list_info();
+        let payload = li
+            .build_update_payload(vec![fset_bool("Done", true)])
+            .await
+            .unwrap();
+        assert_eq!(
+            payload,
+            json!({ "00000000-0000-0000-0000-000000000004_checked": true })
+        );
+        // read it back from an entry with the payload's values
+        let item = li.new_item(test_entry(payload));
+        assert_eq!(item.get_bool_value("Done").unwrap(), Some(true));
+        assert!(li.validate_values(&[fset_bool("Name", true)]).is_err());
+    }
+
+    #[test]
+    fn test_aggregate_op() {
+        let values = [2.0, 4.0, 9.0];
+        assert_eq!(AggregateOp::Sum.apply(&values), Some(15.0));
+        assert_eq!(AggregateOp::Avg.apply(&values), Some(5.0));
+        assert_eq!(AggregateOp::Min.apply(&values), Some(2.0));
+        assert_eq!(AggregateOp::Max.apply(&values), Some(9.0));
+        assert_eq!(AggregateOp::Count.apply(&values), Some(3.0));
+        assert_eq!(AggregateOp::Sum.apply(&[]), Some(0.0));
+        assert_eq!(AggregateOp::Avg.apply(&[]), None);
+        assert_eq!(AggregateOp::Max.apply(&[]), None);
+    }
+
+    #[test]
+    fn test_schema_differs() {
+        let li = test_list_info();
+        let mut fields = vec![
+            test_field(3, "Due", 4, json!({})),
+            test_field(1, "Title", 1, json!({})),
+            test_field(2, "Count", 2, json!({})),
+        ];
+        assert!(!li.schema_differs(&fields), "reordered and renamed");
+        fields[2] = test_field(2, "Count", 1, json!({}));
+        assert!(li.schema_differs(&fields), "changed category");
+        fields.pop();
+        assert!(li.schema_differs(&fields), "removed field");
+    }
+
+    #[test]
+    fn test_validate_values() {
+        let li = test_list_info();
+        assert!(li
+            .validate_values(&[fset_s("Name", "hello"), fset_s("Count", "12")])
+            .is_ok());
+        let errors = li
+            .validate_values(&[
+                fset_s("Missing", "x"),
+                fset_s("Name", "hello"),
+                fset_s("Count", "twelve"),
+            ])
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_task_element() {
+        assert!(test_list_info().task_element().is_none());
+        assert_eq!(test_task_list_info().task_element().map(|f| f.id), Some(4));
+    }
+
+    #[test]
+    fn test_task_done_payload() {
+        assert!(
+            test_list_info().task_done_payload(true).is_err(),
+            "not a task list"
+        );
+        assert_eq!(
+            test_task_list_info().task_done_payload(false).unwrap(),
+            json!({ "00000000-0000-0000-0000-000000000004_checked": false })
+        );
+        // categories task element
+        let status = |choices: &[&str]| {
+            let mut li = test_list_info_with(vec![test_categories_field(7, "Status", choices)]);
+            li.list.settings =
+                serde_json::from_value(json!({ "tasks": { "elementId": 7 } })).unwrap();
+            li
+        };
+        let key = "00000000-0000-0000-0000-000000000007_categories";
+        let li = status(&["Blocked", "To Do", "Done"]);
+        assert_eq!(li.task_done_payload(true).unwrap(), json!({ key: [3] }));
+        assert_eq!(li.task_done_payload(false).unwrap(), json!({ key: [2] }));
+        let li = status(&["Blocked", "Done"]);
+        assert_eq!(li.task_done_payload(false).unwrap(), json!({ key: [] }));
+    }
+
+    #[test]
+    fn test_conflicting_update_actions() {
+        let mut li = test_list_info();
+        li.fields
+            .push(test_field(5, "Owners", 14, json!({ "multiple": true })));
+        li.fields
+            .push(test_field(6, "Reviewers", 14, json!({ "multiple": true })));
+        assert!(li
+            .validate_values(&[
+                fup_vid("Owners", vec![1], UpdateAction::Append),
+                fup_vid("Reviewers", vec![2], UpdateAction::Append),
+            ])
+            .is_ok());
+        let errors = li
+            .validate_values(&[
+                fup_vid("Owners", vec![1], UpdateAction::Append),
+                fup_vid("Reviewers", vec![2], UpdateAction::Replace),
+            ])
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_build_update_payload_invalid_choice() {
+        let mut li = test_list_info();
+        li.fields
+            .push(test_categories_field(7, "Status", &["Open", "Closed"]));
+        li.fields.push(test_field(8, "Owner", 14, json!({})));
+        assert!(li
+            .build_update_payload(vec![fset_s("Name", "x"), fset_s("Status", "Closed")])
+            .await
+            .is_ok());
+        // the invalid choice is reported before the person name is looked up,
+        // which would fail without a client
+        let err = li
+            .build_update_payload(vec![fset_s("Owner", "someone"), fset_s("Status", "Bogus")])
+            .await
+            .unwrap_err();
+        assert!(format!("{}", err).contains("Invalid choice"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_build_update_payload_invalid_field() {
+        let li = test_list_info();
+        assert!(li
+            .build_update_payload(vec![fset_s("Missing", "x")])
+            .await
+            .is_err());
+    }
+}
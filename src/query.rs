@@ -0,0 +1,185 @@
+//! High-level list query, combining filter, sort order, grouping, and paging.
+//! Use with ListInfo::query, which calls the appropriate Zenkit endpoint
+//! based on the options that are set.
+//!
+//! ```rust
+//! use zenkit::{query::Query, types::SortDirection};
+//! let q = Query::new().order_by("updated_at", SortDirection::Desc).limit(20);
+//! ```
+
+use crate::{
+    types::{GetEntriesRequest, GetEntriesViewRequest, JsonMap, OrderBy, SortDirection, ID},
+    Error,
+};
+use serde_json::Value;
+
+/// Number of entries requested per page, if not configured in QueryDefaults
+pub(crate) const DEFAULT_PAGE_SIZE: usize = 500;
+
+/// Default query settings, set in ApiConfig.
+/// These apply to ListInfo::get_items and ListInfo::query, unless overridden.
+#[derive(Debug, Clone)]
+pub struct QueryDefaults {
+    /// whether to include deprecated entries. Default false.
+    pub allow_deprecated: bool,
+    /// number of entries per request. Default 500.
+    pub page_size: usize,
+}
+
+impl Default for QueryDefaults {
+    fn default() -> Self {
+        Self {
+            allow_deprecated: false,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+/// Query parameters for list entries
+#[derive(Debug, Default)]
+pub struct Query {
+    filter: Option<Value>,
+    order_by: Vec<OrderBy>,
+    group_by: Option<ID>,
+    task_style: bool,
+    allow_deprecated: Option<bool>,
+    limit: Option<usize>,
+    skip: usize,
+}
+
+impl Query {
+    /// Constructs a query for all entries of the list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the filter expression (see filter::FilterBuilder)
+    pub fn filter(mut self, filter: Value) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Adds a sort column. Can't be combined with group_by or task_style.
+    pub fn order_by(mut self, column: &str, direction: SortDirection) -> Self {
+        self.order_by.push(OrderBy {
+            column: Some(column.to_string()),
+            direction,
+        });
+        self
+    }
+
+    /// Groups entries by a persons or categories field
+    pub fn group_by(mut self, element_id: ID) -> Self {
+        self.group_by = Some(element_id);
+        self
+    }
+
+    /// Divides entries into todo and done groups. Requires a list with the task addon.
+    pub fn task_style(mut self, task_style: bool) -> Self {
+        self.task_style = task_style;
+        self
+    }
+
+    /// Includes deprecated entries. If not set, QueryDefaults.allow_deprecated is used.
+    pub fn allow_deprecated(mut self, allow_deprecated: bool) -> Self {
+        self.allow_deprecated = Some(allow_deprecated);
+        self
+    }
+
+    /// Sets the maximum number of entries to return (0 for no limit).
+    /// If not set, QueryDefaults.page_size is used.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the number of entries to skip
+    pub fn skip(mut self, skip: usize) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Returns true if the query requires the list view endpoint (grouping or task style),
+    /// or an error if the query combines sorting with grouping or task style.
+    pub(crate) fn uses_view(&self) -> Result<bool, Error> {
+        let view = self.group_by.is_some() || self.task_style;
+        if view && !self.order_by.is_empty() {
+            return Err(Error::Other(
+                "Query order_by can't be combined with group_by or task_style".to_string(),
+            ));
+        }
+        Ok(view)
+    }
+
+    fn filter_value(&self) -> Value {
+        self.filter
+            .clone()
+            .unwrap_or_else(|| Value::Object(JsonMap::new()))
+    }
+
+    /// Returns parameters for get_list_entries
+    pub(crate) fn entries_request(&self, defaults: &QueryDefaults) -> GetEntriesRequest {
+        GetEntriesRequest {
+            filter: self.filter_value(),
+            limit: self.limit.unwrap_or(defaults.page_size),
+            skip: self.skip,
+            allow_deprecated: self.allow_deprecated.unwrap_or(defaults.allow_deprecated),
+            order_by: self.order_by.clone(),
+        }
+    }
+
+    /// Returns parameters for get_list_entries_for_view
+    pub(crate) fn view_request(&self, defaults: &QueryDefaults) -> GetEntriesViewRequest {
+        GetEntriesViewRequest {
+            filter: self.filter_value(),
+            group_by_element_id: self.group_by.unwrap_or_default(),
+            limit: self.limit.unwrap_or(defaults.page_size) as u64,
+            skip: self.skip as u64,
+            allow_deprecated: self.allow_deprecated.unwrap_or(defaults.allow_deprecated),
+            task_style: self.task_style,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Query, QueryDefaults};
+    use crate::types::SortDirection;
+
+    #[test]
+    fn test_query_endpoint() {
+        let defaults = QueryDefaults::default();
+        let sorted = Query::new().order_by("updated_at", SortDirection::Asc);
+        assert!(!sorted.uses_view().unwrap());
+        assert_eq!(sorted.entries_request(&defaults).order_by.len(), 1);
+
+        let grouped = Query::new().group_by(7).limit(10);
+        assert!(grouped.uses_view().unwrap());
+        assert_eq!(grouped.view_request(&defaults).group_by_element_id, 7);
+        assert_eq!(grouped.view_request(&defaults).limit, 10);
+
+        assert!(Query::new()
+            .task_style(true)
+            .order_by("updated_at", SortDirection::Asc)
+            .uses_view()
+            .is_err());
+    }
+
+    #[test]
+    fn test_query_defaults() {
+        let defaults = QueryDefaults {
+            allow_deprecated: true,
+            page_size: 200,
+        };
+        let req = Query::new().entries_request(&defaults);
+        assert_eq!(req.limit, 200);
+        assert!(req.allow_deprecated);
+
+        let req = Query::new()
+            .limit(5)
+            .allow_deprecated(false)
+            .entries_request(&defaults);
+        assert_eq!(req.limit, 5);
+        assert!(!req.allow_deprecated);
+    }
+}
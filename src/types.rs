@@ -7,6 +7,7 @@
 //! - ListInfo - wraps a List with its field definitions, and contains business field getters and setters.
 //! - Item - wraps a list Entry, and has getters and setters to simplify access to business fields.
 //!   (derefs to Entry)
+//! - ItemRef - lightweight id/uuid/title handle to an Entry or Item, for caching.
 //! - Various structures whose names have a suffix of 'Request' or 'Response', for api parameters and
 //!   responses.
 //! - ChangedArray,ChangedValue - describe data changed inside an Activity object
@@ -14,7 +15,7 @@
 //! All struct, enum and field names follow Rust naming and capitalization convention,
 //!   (Pascal case for struct/enum names, snake_case for field names)
 //!   Serde rules are used to map to/from the json-defined names on a per-field/per-struct basis
-//! All color fields use 'color' (no _hex suffix)
+//! All color fields use 'color' (no _hex suffix), and have type Color
 //! Whenever I felt fairly confident that a type could be made more specific, I did so
 //!   (String -> UUID, int -> ID, String -> DateTime<Utc>), etc.
 
@@ -24,17 +25,27 @@ use num_traits::FromPrimitive;
 use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
 use serde_repr::{Deserialize_repr, Serialize_repr};
-use std::{clone::Clone, default::Default, fmt, iter::Iterator, str::FromStr};
+use std::{
+    clone::Clone,
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    default::Default,
+    fmt,
+    iter::Iterator,
+    str::FromStr,
+    sync::Arc,
+};
 
 // re-export
-pub use crate::datetime::{DateTime, Utc};
+pub use crate::datetime::{DateTime, NaiveDate, Utc};
 
 // re-export from item and list
 pub use crate::{
-    item::Item,
+    item::{Item, ItemRef},
     list::{
-        fset_f, fset_i, fset_id, fset_s, fset_t, fset_vid, fset_vs, fup_f, fup_i, fup_id, fup_s,
-        fup_t, fup_vid, fup_vs, FieldSetVal, FieldVal, ListInfo,
+        fset_bool, fset_date, fset_date_range, fset_datetime, fset_f, fset_i, fset_id, fset_s,
+        fset_t, fset_vid, fset_vs, fup_bool, fup_datetime, fup_f, fup_i, fup_id, fup_s, fup_t,
+        fup_vid, fup_vs, AggregateOp, FieldSetVal, FieldVal, ListInfo,
     },
 };
 
@@ -47,7 +58,6 @@ pub type UUID = String; // RFC 4122
 /// Error code returned from zenkit api calls. See https://base.zenkit.com/docs/api/type/errorcode
 pub type ErrorCode = String;
 
-//pub type ListSettings = Value;
 //pub type Notification = Value;
 /// string-indexed map of json values
 pub type JsonMap = serde_json::map::Map<String, Value>;
@@ -134,6 +144,14 @@ impl From<&'_ str> for AllId {
     }
 }
 
+/// Available with the "uuid" feature
+#[cfg(feature = "uuid")]
+impl From<uuid::Uuid> for AllId {
+    fn from(u: uuid::Uuid) -> AllId {
+        AllId::UUID(u.to_string())
+    }
+}
+
 //impl Into<String> for AllId {
 //    fn into(self) -> String {
 //       self.to_string()
@@ -141,7 +159,7 @@ impl From<&'_ str> for AllId {
 //}
 
 /// Sort direction
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
 #[serde(rename_all = "lowercase")]
 pub enum SortDirection {
     /// Ascending
@@ -204,6 +222,83 @@ pub enum ElementCategoryId {
     Dependencies = 19,
 }
 
+/// Hex color ("rrggbb", "rgb", "#rrggbb", or "#rgb"), as received from Zenkit.
+/// Colors are not validated when deserialized, since Zenkit may send an empty,
+/// null, or invalid value; as_rgb returns None for those.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+#[serde(from = "UncheckedColor", into = "String")]
+pub struct Color(String);
+
+impl Color {
+    /// Constructs a color from a hex string of three or six digits,
+    /// with or without a leading '#'. Returns an error if the string is not a valid color.
+    pub fn new<S: Into<String>>(hex: S) -> Result<Self, Error> {
+        let color = Color(hex.into());
+        match color.as_rgb() {
+            Some(_) => Ok(color),
+            None => Err(Error::Other(format!("Invalid hex color '{}'", color.0))),
+        }
+    }
+
+    /// Returns the color as a hex string, as received from Zenkit
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the red, green, and blue components, or None if the color
+    /// is not three or six hex digits (with optional leading '#')
+    pub fn as_rgb(&self) -> Option<(u8, u8, u8)> {
+        let digits = self.0.strip_prefix('#').unwrap_or(&self.0);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let component = |s: &str| u8::from_str_radix(s, 16).ok();
+        match digits.len() {
+            6 => Some((
+                component(&digits[0..2])?,
+                component(&digits[2..4])?,
+                component(&digits[4..6])?,
+            )),
+            // shorthand "rgb" is "rrggbb"
+            3 => {
+                let c = |i: usize| component(&digits[i..i + 1]).map(|v| v * 17);
+                Some((c(0)?, c(1)?, c(2)?))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = Error;
+
+    fn try_from(hex: String) -> Result<Self, Error> {
+        Color::new(hex)
+    }
+}
+
+// Color as received from Zenkit, which may be null or invalid
+#[derive(Deserialize)]
+struct UncheckedColor(Option<String>);
+
+impl From<UncheckedColor> for Color {
+    fn from(hex: UncheckedColor) -> Color {
+        Color(hex.0.unwrap_or_default())
+    }
+}
+
+impl From<Color> for String {
+    fn from(color: Color) -> String {
+        color.0
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// for elements of type Category, PredefinedCategory defines the choices
 #[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct PredefinedCategory {
@@ -216,9 +311,9 @@ pub struct PredefinedCategory {
     pub uuid: UUID,
     /// category name
     pub name: String,
-    /// color, in hex
-    #[serde(rename = "colorHex")]
-    pub color: String,
+    /// color, in hex. Empty if Zenkit sent no color
+    #[serde(rename = "colorHex", default)]
+    pub color: Color,
     /// date created
     pub created_at: DateTime<Utc>,
     /// date updated
@@ -244,6 +339,12 @@ pub struct PredefinedCategory {
     pub sort_order: f32,
 }
 
+impl fmt::Display for PredefinedCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.id, self.name, self.color)
+    }
+}
+
 impl ZKObjectID for PredefinedCategory {
     fn get_id(&self) -> ID {
         self.id
@@ -335,7 +436,7 @@ pub struct ElementDataCategories {
 }
 
 /// Sort order for filtered query
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct OrderBy {
     /// Column name
     pub column: Option<String>,
@@ -345,7 +446,10 @@ pub struct OrderBy {
     pub direction: SortDirection,
 }
 
-/// Parameters for get_list_entries
+/// Parameters for get_list_entries.
+/// Zenkit does not support expanding related objects: persons and categories fields
+/// of returned entries contain ids and names only. For full objects, see
+/// Item::get_persons, and the field definitions in ListInfo for categories.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct GetEntriesRequest {
     /// filter object to filter the response
@@ -434,12 +538,32 @@ pub struct FilterCountData {
 pub struct GetEntriesViewResponse {
     /// number of filtered items returned
     pub count_data: FilterCountData,
-    /// per group counts (todo, done, etc.)
-    pub count_data_per_group: Vec<FilterCountData>,
+    /// per group counts, keyed by group (for task_style, "todo" and "done")
+    #[serde(default, deserialize_with = "group_counts")]
+    pub count_data_per_group: HashMap<String, FilterCountData>,
     /// entries returned
     pub list_entries: Vec<Entry>,
 }
 
+/// Deserializes countDataPerGroup, which is normally an object keyed by group.
+/// An array is also accepted, keyed by position ("0", "1", ...), and null as empty.
+fn group_counts<'de, D>(deserializer: D) -> Result<HashMap<String, FilterCountData>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error as _;
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(HashMap::new()),
+        Value::Array(arr) => arr
+            .into_iter()
+            .enumerate()
+            .map(|(n, v)| Ok((n.to_string(), serde_json::from_value(v)?)))
+            .collect::<Result<_, serde_json::Error>>()
+            .map_err(D::Error::custom),
+        v => serde_json::from_value(v).map_err(D::Error::custom),
+    }
+}
+
 /// Error details returned from Zenkit
 //noinspection SpellCheckingInspection
 #[derive(Deserialize, Debug)]
@@ -465,7 +589,7 @@ pub struct ErrorResult {
     pub error: ErrorInfo,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
 #[serde(rename_all = "camelCase")]
 pub enum RoleID {
     ListOwner,
@@ -482,6 +606,27 @@ pub enum RoleID {
     OrganizationUser,
 }
 
+impl RoleID {
+    /// Returns false for read-only and comment-only roles
+    pub fn can_write(&self) -> bool {
+        !matches!(
+            self,
+            RoleID::CommentOnlyListUser
+                | RoleID::ReadOnlyListUser
+                | RoleID::CommentOnlyWorkspaceUser
+                | RoleID::ReadOnlyWorkspaceUser
+        )
+    }
+
+    /// Returns false for read-only roles
+    pub fn can_comment(&self) -> bool {
+        !matches!(
+            self,
+            RoleID::ReadOnlyListUser | RoleID::ReadOnlyWorkspaceUser
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum LoginProvider {
@@ -656,6 +801,12 @@ pub struct Element {
     pub visible_in_public_list: Option<bool>,
 }
 
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({})", self.id, self.name, self.element_category)
+    }
+}
+
 impl Element {
     /// Returns the element description, or an empty string if none was provided
     pub fn get_description(&self) -> &str {
@@ -665,6 +816,38 @@ impl Element {
         }
     }
 
+    /// Returns true if the field is shown in public lists. Defaults to false if not set.
+    pub fn is_visible_in_public_list(&self) -> bool {
+        self.visible_in_public_list.unwrap_or(false)
+    }
+
+    /// Returns the choice that marks a task as done: the predefined category named "Done"
+    /// (case-insensitive). Returns None if the field is not a categories field or has no such choice.
+    pub fn done_category(&self) -> Option<&PredefinedCategory> {
+        if self.element_category != ElementCategoryId::Categories {
+            return None;
+        }
+        self.element_data
+            .predefined_categories
+            .as_ref()?
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case("done"))
+    }
+
+    /// Returns the choice that marks a task as not done: the predefined category named
+    /// "To Do" or "Todo" (case-insensitive). Returns None if the field is not a categories
+    /// field or has no such choice.
+    pub fn todo_category(&self) -> Option<&PredefinedCategory> {
+        if self.element_category != ElementCategoryId::Categories {
+            return None;
+        }
+        self.element_data
+            .predefined_categories
+            .as_ref()?
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case("to do") || c.name.eq_ignore_ascii_case("todo"))
+    }
+
     /// lookup choice id from its name or uuid. Returns Error if there is no match
     pub fn get_choice_id(&self, choice_name: &str) -> Result<ID, Error> {
         if self.element_category == ElementCategoryId::Categories {
@@ -935,6 +1118,10 @@ pub struct Activity {
     //pub user_image_link: Option<bool>,
 }
 
+/// Comment on a list or list entry. Comments are activities
+/// with activity_type Comment, and the text in `message`.
+pub type Comment = Activity;
+
 impl ZKObjectID for Activity {
     fn get_id(&self) -> ID {
         self.id
@@ -944,6 +1131,63 @@ impl ZKObjectID for Activity {
     }
 }
 
+impl Activity {
+    /// Returns true if the activity has a parent (parent_uuid is set),
+    /// for example, a comment that replies to another comment
+    pub fn is_reply(&self) -> bool {
+        self.parent_uuid.is_some()
+    }
+}
+
+/// Comment with its replies
+#[derive(Debug)]
+pub struct CommentThread {
+    /// the comment
+    pub comment: Comment,
+    /// replies to the comment, each with their own replies
+    pub replies: Vec<CommentThread>,
+}
+
+/// Nests comments under their parent comment, using parent_uuid.
+/// An activity is treated as a reply only if its parent is also in the list;
+/// all others are returned as top-level threads, in their original order.
+pub fn group_comment_threads(activities: Vec<Activity>) -> Vec<CommentThread> {
+    let uuids: HashSet<UUID> = activities.iter().map(|a| a.uuid.clone()).collect();
+    let mut replies: HashMap<UUID, Vec<Activity>> = HashMap::new();
+    let mut top_level = Vec::new();
+    for activity in activities.into_iter() {
+        match &activity.parent_uuid {
+            Some(parent) if parent != &activity.uuid && uuids.contains(parent) => {
+                replies.entry(parent.clone()).or_default().push(activity)
+            }
+            _ => top_level.push(activity),
+        }
+    }
+    let mut threads: Vec<CommentThread> = top_level
+        .into_iter()
+        .map(|a| comment_thread(a, &mut replies))
+        .collect();
+    // replies that form a cycle have no top-level ancestor; don't drop them
+    for (_, rest) in replies.drain() {
+        threads.extend(rest.into_iter().map(|comment| CommentThread {
+            comment,
+            replies: Vec::new(),
+        }));
+    }
+    threads
+}
+
+fn comment_thread(comment: Activity, replies: &mut HashMap<UUID, Vec<Activity>>) -> CommentThread {
+    let children = replies.remove(&comment.uuid).unwrap_or_default();
+    CommentThread {
+        comment,
+        replies: children
+            .into_iter()
+            .map(|c| comment_thread(c, replies))
+            .collect(),
+    }
+}
+
 /// Original and new value
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -980,8 +1224,10 @@ pub struct ChangedArray<T> {
     /// values after change
     pub value_to: Vec<T>,
     /// values before change, in string representation
+    #[serde(default)]
     pub value_from_as_strings: Vec<String>,
     /// values after change, in string representation
+    #[serde(default)]
     pub value_to_as_strings: Vec<String>,
 }
 
@@ -1010,6 +1256,19 @@ pub struct DateValue {
     pub duration: Option<String>, // ex: "4 day"
 }
 
+/// File attachment, in a changed Files field
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FileRef {
+    /// file id
+    pub id: ID,
+    /// file name
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    /// catch-all for other file fields
+    #[serde(flatten)]
+    pub fields: JsonMap,
+}
+
 /// ChangedData, within an Activity, represents the previous and new data values.
 /// Not all field types have been implemented as structs; the other field types
 /// have change data as a (serde_json) Value.
@@ -1031,6 +1290,8 @@ pub enum ChangedData {
     Persons(ChangedArray<ID>),
     /// change to reference(s)
     References(ChangedArray<UUID>),
+    /// change to file attachment(s)
+    Files(ChangedArray<FileRef>),
     /// change not covered by any of the other types
     Other(Value),
 }
@@ -1043,6 +1304,14 @@ fn opt_to_string<T: ToString>(v: &Option<T>) -> String {
 }
 
 impl ChangedData {
+    /// Returns printable strings for the previous and new values
+    pub fn from_to_strings(&self) -> (String, String) {
+        (
+            self.val_to_string(FromTo::From),
+            self.val_to_string(FromTo::To),
+        )
+    }
+
     /// Generate printable string value for from (previous) or to (new) value
     pub fn val_to_string(&self, ft: FromTo) -> String {
         use crate::join;
@@ -1067,9 +1336,32 @@ impl ChangedData {
             ChangedData::Categories(arr) => join(",", arr.as_strings(ft)),
             ChangedData::Persons(arr) => join(",", arr.as_strings(ft)),
             ChangedData::References(arr) => join(",", arr.get(ft)),
+            ChangedData::Files(arr) => join(
+                ",",
+                &arr.get(ft)
+                    .iter()
+                    .map(|f| f.file_name.as_str())
+                    .collect::<Vec<&str>>(),
+            ),
             ChangedData::Other(_) => "<value>".to_string(),
         }
     }
+
+    /// Returns the previous and new values as json, suitable for storage.
+    /// Typed changes are serialized with their from/to values; Other is passed through.
+    pub fn to_json(&self) -> Value {
+        match self {
+            ChangedData::Text(val) => serde_json::to_value(val),
+            ChangedData::Number(val) => serde_json::to_value(val),
+            ChangedData::Date(val) => serde_json::to_value(val),
+            ChangedData::Categories(arr) => serde_json::to_value(arr),
+            ChangedData::Persons(arr) => serde_json::to_value(arr),
+            ChangedData::References(arr) => serde_json::to_value(arr),
+            ChangedData::Files(arr) => serde_json::to_value(arr),
+            ChangedData::Other(val) => Ok(val.clone()),
+        }
+        .unwrap_or_default()
+    }
 }
 
 /// change of object field
@@ -1148,9 +1440,9 @@ pub struct Background {
     pub target_id: ID,
     /// file associated with this background
     pub file_id: ShortId,
-    /// background color, in hex
-    #[serde(rename = "color_hex")]
-    pub color: String,
+    /// background color, in hex. Empty for backgrounds without a color
+    #[serde(rename = "color_hex", default)]
+    pub color: Color,
     pub theme: BackgroundTheme,
     pub style: BackgroundStyle,
     pub description: String,
@@ -1170,7 +1462,7 @@ impl ZKObjectID for Background {
 }
 
 /// item in a checklist
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub struct ChecklistItem {
     /// true if item is checked
     pub checked: bool,
@@ -1179,7 +1471,7 @@ pub struct ChecklistItem {
 }
 
 /// Checklist field
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Checklist {
     /// checklist uuid
@@ -1331,6 +1623,29 @@ impl Entry {
         self.map_values(field_uuid, "categories_sort", "name", |v| v.as_str())
     }
 
+    /// Returns the depth of the entry in a hierarchy list (0 for top-level entries),
+    /// from the "{uuid}_level" value of the hierarchy field. This value is not documented
+    /// by Zenkit; None is returned if it is absent.
+    pub fn hierarchy_level(&self, field_uuid: &str) -> Option<u32> {
+        let field_name = format!("{}_level", field_uuid);
+        self.fields
+            .get(&field_name)
+            .and_then(|v| v.as_u64())
+            .map(|n| n as u32)
+    }
+
+    /// Returns value of checkbox field. Unchecked boxes may have no value.
+    pub fn get_checked(&self, field_uuid: &str) -> Option<bool> {
+        let field_name = format!("{}_checked", field_uuid);
+        self.fields.get(&field_name).and_then(|v| v.as_bool())
+    }
+
+    /// Returns value of checkbox field, or None if undefined.
+    /// Same as get_checked, named for consistency with Item::get_bool_value.
+    pub fn get_bool_value(&self, field_uuid: &str) -> Result<Option<bool>, Error> {
+        Ok(self.get_checked(field_uuid))
+    }
+
     /// Returns label/category ids
     pub fn get_category_ids(&self, field_uuid: &str) -> Vec<ID> {
         self.map_values(field_uuid, "categories_sort", "id", |v| v.as_u64())
@@ -1352,6 +1667,58 @@ impl Entry {
         self.map_values(field_uuid, "references_sort", "uuid", |v| v.as_str())
     }
 
+    /// Returns files attached in a files field, from the "{uuid}_files" array.
+    /// Returns an empty array if the field has no files, or an error if
+    /// the array does not contain file objects.
+    pub fn get_files(&self, field_uuid: &str) -> Result<Vec<File>, Error> {
+        let field_name = format!("{}_files", field_uuid);
+        match self.fields.get(&field_name) {
+            Some(Value::Array(files)) => files
+                .iter()
+                .map(|f| serde_json::from_value(f.clone()).map_err(crate::util::json_error::<File>))
+                .collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Returns the computed value of a formula field, or None if there is no value.
+    /// The result is stored with a suffix that depends on the formula's result type
+    /// ("{uuid}_number", "{uuid}_text", or "{uuid}_date"), so the value may be a number
+    /// or a string. Null values are returned as None.
+    pub fn get_formula_value(&self, field_uuid: &str) -> Result<Option<Value>, Error> {
+        Ok(["number", "text", "date"]
+            .iter()
+            .filter_map(|suffix| self.fields.get(&format!("{}_{}", field_uuid, suffix)))
+            .find(|v| !v.is_null())
+            .cloned())
+    }
+
+    /// Returns the value of a mirror field (a value rolled up from referenced items),
+    /// or None if there is no value. If the field has values with several key suffixes,
+    /// the first in suffix order is returned; see get_mirror_values.
+    pub fn get_mirror_value(&self, field_uuid: &str) -> Option<&Value> {
+        self.get_mirror_values(field_uuid)
+            .first()
+            .map(|(_, value)| *value)
+    }
+
+    /// Returns the values of a mirror field (values rolled up from referenced items).
+    /// Mirror values are stored with the field uuid and a suffix that depends on the type
+    /// of the mirrored field (for example, "{uuid}_text" or "{uuid}_references_sort"),
+    /// and a mirror field may have several, so all are returned, as (suffix, value) pairs
+    /// sorted by suffix. Null values are omitted.
+    pub fn get_mirror_values(&self, field_uuid: &str) -> Vec<(&str, &Value)> {
+        let prefix = format!("{}_", field_uuid);
+        let mut values: Vec<(&str, &Value)> = self
+            .fields
+            .iter()
+            .filter(|(_, v)| !v.is_null())
+            .filter_map(|(k, v)| k.strip_prefix(&prefix).map(|suffix| (suffix, v)))
+            .collect();
+        values.sort_by_key(|(suffix, _)| *suffix);
+        values
+    }
+
     // obtain Vec<value> from array of maps of key-value
     fn map_values<'v, T>(
         &'v self,
@@ -1407,6 +1774,14 @@ pub struct DeleteListEntryResponse {
     pub list_entry: DeleteListEntryDetail,
 }
 
+/// Response from bulk delete entries
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteListEntriesResponse {
+    /// number of entries deleted
+    pub count: u64,
+}
+
 /// File attachment
 //noinspection SpellCheckingInspection
 #[derive(Serialize, Deserialize, Debug)]
@@ -1435,8 +1810,8 @@ pub struct File {
     /// file url
     #[serde(rename = "fileUrl")]
     pub file_url: Option<String>,
-    /// crop parameters
-    #[serde(rename = "cropParams")]
+    /// crop parameters. See also crop()
+    #[serde(rename = "cropParams", default)]
     pub crop_params: Value,
     // I uploaded an image and both height and width were null
     // their size was in metadata.height, metadata.width
@@ -1458,7 +1833,7 @@ pub struct File {
     #[serde(rename = "elementId")]
     pub element_id: ID,
     /// queries
-    #[serde(rename = "cachedQuerys")]
+    #[serde(rename = "cachedQuerys", default)]
     pub cached_queries: Value, // note spelling change
     /// error during import
     #[serde(rename = "importError")]
@@ -1479,6 +1854,26 @@ impl ZKObjectID for File {
     }
 }
 
+impl File {
+    /// Returns the crop area selected for an image, or None if the image is not cropped
+    pub fn crop(&self) -> Option<CropParams> {
+        serde_json::from_value(self.crop_params.clone()).ok()
+    }
+}
+
+/// Crop area of an image, in pixels
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy)]
+pub struct CropParams {
+    /// left edge
+    pub x: f64,
+    /// top edge
+    pub y: f64,
+    /// crop width
+    pub width: f64,
+    /// crop height
+    pub height: f64,
+}
+
 /// Filter expression term
 //noinspection SpellCheckingInspection
 #[derive(Serialize, Deserialize, Debug)]
@@ -1584,7 +1979,7 @@ pub struct List {
     ///
     #[serde(rename = "originData")]
     pub origin_data: Option<Value>,
-    ///
+    /// default view. See also view_modus()
     #[serde(rename = "defaultViewModus")]
     pub default_view_modus: i64,
     /// date list created
@@ -1605,7 +2000,7 @@ pub struct List {
     #[serde(rename = "backgroundId")]
     ///
     pub background_id: Option<String>,
-    ///
+    /// list visibility. See also list_visibility()
     pub visibility: i64,
     ///
     #[serde(rename = "iconColor")]
@@ -1615,7 +2010,8 @@ pub struct List {
     pub icon_background_color: Option<String>, // undocumented
     /// id of user that created list
     pub created_by: ID,
-    //pub settings: Option<Value>, // undocumented
+    /// list settings (undocumented)
+    pub settings: Option<ListSettings>,
     //#[serde(rename = "resourceTags")]
     //pub resource_tags: Vec<ResourceTag>, // undocumented
     //     { appType: String, created_at: DateTime<Utc>, created_by: ID, is_owner: bool, tag: String,
@@ -1638,6 +2034,67 @@ impl List {
     pub fn has_id(&self, id: &str) -> bool {
         self.uuid == id || self.name == id || self.short_id == id || self.id.to_string() == id
     }
+
+    /// Returns the list's default view
+    pub fn view_modus(&self) -> ViewModus {
+        ViewModus::from(self.default_view_modus)
+    }
+
+    /// Returns the list visibility
+    pub fn list_visibility(&self) -> ListVisibility {
+        ListVisibility::from(self.visibility)
+    }
+
+    /// Returns the user that created the list, from the (cached) user list of the
+    /// list's workspace. Returns None if the user is no longer a member of the workspace.
+    pub async fn creator(&self, api: &crate::ApiClient) -> Result<Option<Arc<User>>, Error> {
+        let created_by = self.created_by;
+        api.find_user(self.workspace_id, |u| u.id == created_by)
+            .await
+    }
+
+    /// Returns the source of an imported list (e.g., from Trello or Asana),
+    /// or None if the list was not imported
+    pub fn origin(&self) -> Option<Origin> {
+        match &self.origin_provider {
+            Some(provider) if !provider.is_empty() => Some(Origin {
+                provider: provider.clone(),
+                data: self.origin_data.clone().unwrap_or_default(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// List settings. These are not documented by Zenkit; only task settings are parsed,
+/// and all other settings are in the catch-all 'fields'.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct ListSettings {
+    /// task settings, present if the task addon is activated for the list
+    pub tasks: Option<TaskSettings>,
+    /// All other settings go into the catch-all 'fields'
+    #[serde(flatten)]
+    pub fields: JsonMap,
+}
+
+/// Task settings of a task list
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct TaskSettings {
+    /// id of the element (checkbox or categories) that holds the todo/done state
+    #[serde(rename = "elementId")]
+    pub element_id: Option<ID>,
+    /// All other settings go into the catch-all 'fields'
+    #[serde(flatten)]
+    pub fields: JsonMap,
+}
+
+/// Source of an imported list
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct Origin {
+    /// name of the provider the list was imported from
+    pub provider: String,
+    /// provider-specific data, such as the id of the source board
+    pub data: Value,
 }
 
 impl fmt::Display for List {
@@ -1653,11 +2110,102 @@ pub struct ListPrototype {
     pub name: String,
 }
 
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
-#[repr(u8)]
+/// Changes to list properties, for ApiClient::update_list. Properties that are None are unchanged.
+#[derive(Serialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUpdate {
+    /// list name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// list description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// name for a list item, such as "Task"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_name: Option<String>,
+    /// plural name for list items, such as "Tasks"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_name_plural: Option<String>,
+}
+
+/// Who can see a list. See List.list_visibility()
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(from = "i64", into = "i64")]
 pub enum ListVisibility {
-    ListMembersOnly = 0,
-    ListMembersAndWorkspaceMembers = 1,
+    /// only list members (0)
+    ListMembersOnly,
+    /// list members and workspace members (1)
+    ListMembersAndWorkspaceMembers,
+    /// unrecognized value
+    Unknown(i64),
+}
+
+impl From<i64> for ListVisibility {
+    fn from(n: i64) -> Self {
+        match n {
+            0 => ListVisibility::ListMembersOnly,
+            1 => ListVisibility::ListMembersAndWorkspaceMembers,
+            _ => ListVisibility::Unknown(n),
+        }
+    }
+}
+
+impl From<ListVisibility> for i64 {
+    fn from(v: ListVisibility) -> i64 {
+        match v {
+            ListVisibility::ListMembersOnly => 0,
+            ListVisibility::ListMembersAndWorkspaceMembers => 1,
+            ListVisibility::Unknown(n) => n,
+        }
+    }
+}
+
+/// Default view of a list. See List.view_modus()
+#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[serde(from = "i64", into = "i64")]
+pub enum ViewModus {
+    /// Kanban board (0)
+    Kanban,
+    /// Table (1)
+    Table,
+    /// Calendar (2)
+    Calendar,
+    /// List (3)
+    List,
+    /// Mind map (4)
+    MindMap,
+    /// Gantt chart/timeline (5)
+    Gantt,
+    /// unrecognized value
+    Unknown(i64),
+}
+
+impl From<i64> for ViewModus {
+    fn from(n: i64) -> Self {
+        match n {
+            0 => ViewModus::Kanban,
+            1 => ViewModus::Table,
+            2 => ViewModus::Calendar,
+            3 => ViewModus::List,
+            4 => ViewModus::MindMap,
+            5 => ViewModus::Gantt,
+            _ => ViewModus::Unknown(n),
+        }
+    }
+}
+
+impl From<ViewModus> for i64 {
+    fn from(v: ViewModus) -> i64 {
+        match v {
+            ViewModus::Kanban => 0,
+            ViewModus::Table => 1,
+            ViewModus::Calendar => 2,
+            ViewModus::List => 3,
+            ViewModus::MindMap => 4,
+            ViewModus::Gantt => 5,
+            ViewModus::Unknown(n) => n,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -1757,7 +2305,8 @@ pub struct Workspace {
     /// workspace creator user id
     pub created_by: ID,
 
-    /// lists in workspace
+    /// lists in workspace. Empty if lists were not loaded (see ApiClient::get_workspaces)
+    #[serde(default)]
     pub lists: Vec<List>,
     // undocumented fields seen in output
     //#[serde(rename = "resourceTags")]
@@ -1785,6 +2334,13 @@ impl Workspace {
         }
     }
 
+    /// Returns the user that created the workspace, from the (cached) workspace user list.
+    /// Returns None if the user is no longer a member of the workspace.
+    pub async fn creator(&self, api: &crate::ApiClient) -> Result<Option<Arc<User>>, Error> {
+        let created_by = self.created_by;
+        api.find_user(self.id, |u| u.id == created_by).await
+    }
+
     /// Returns true if the workspace has the id, uuid, shortId, or name of the parameter
     pub fn has_id(&self, id: &str) -> bool {
         self.uuid == id || self.name == id || self.short_id == id || self.id.to_string() == id
@@ -1817,7 +2373,7 @@ pub struct ResourceTag {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(PartialEq, Debug, Serialize, Deserialize)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TextFormat {
     Plain,
@@ -1921,6 +2477,78 @@ pub struct NewWebhook {
     pub locale: String,
 }
 
+impl NewWebhook {
+    /// Constructs a webhook for events in a workspace
+    pub fn for_workspace(
+        workspace_id: ID,
+        trigger_type: WebhookTriggerType,
+        url: &str,
+        locale: &str,
+    ) -> Self {
+        Self {
+            workspace_id: Some(workspace_id),
+            ..Self::unscoped(trigger_type, url, locale)
+        }
+    }
+
+    /// Constructs a webhook for events in a list
+    pub fn for_list(
+        list_id: ID,
+        trigger_type: WebhookTriggerType,
+        url: &str,
+        locale: &str,
+    ) -> Self {
+        Self {
+            list_id: Some(list_id),
+            ..Self::unscoped(trigger_type, url, locale)
+        }
+    }
+
+    /// Constructs a webhook for events on a single list entry
+    pub fn for_entry(
+        list_id: ID,
+        list_entry_id: ID,
+        trigger_type: WebhookTriggerType,
+        url: &str,
+        locale: &str,
+    ) -> Self {
+        Self {
+            list_id: Some(list_id),
+            list_entry_id: Some(list_entry_id),
+            ..Self::unscoped(trigger_type, url, locale)
+        }
+    }
+
+    fn unscoped(trigger_type: WebhookTriggerType, url: &str, locale: &str) -> Self {
+        Self {
+            trigger_type,
+            url: url.to_string(),
+            workspace_id: None,
+            list_id: None,
+            list_entry_id: None,
+            element_id: None,
+            locale: locale.to_string(),
+        }
+    }
+
+    /// Checks that the webhook has a consistent scope: workspace can't be combined
+    /// with list, entry, or element, and entry or element require a list.
+    pub fn validate(&self) -> Result<(), Error> {
+        let in_list = self.list_entry_id.is_some() || self.element_id.is_some();
+        if self.workspace_id.is_some() && (self.list_id.is_some() || in_list) {
+            return Err(Error::Other(
+                "Invalid webhook scope: workspace_id can't be combined with list_id, list_entry_id, or element_id".to_string(),
+            ));
+        }
+        if in_list && self.list_id.is_none() {
+            return Err(Error::Other(
+                "Invalid webhook scope: list_entry_id and element_id require list_id".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Application OAuth client configuration
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -1941,6 +2569,22 @@ pub struct OAuthResponse {
     pub client_secret: String,
 }
 
+/// Result of global_search
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// id of list containing the match
+    pub list_id: ID,
+    /// id of matching entry, or None if the list itself matched
+    pub list_entry_id: Option<ID>,
+    /// matched text, with surrounding context
+    #[serde(default)]
+    pub snippet: String,
+    /// catch-all for other fields
+    #[serde(flatten)]
+    pub fields: JsonMap,
+}
+
 /// Return value from get_shared_access
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -1992,13 +2636,21 @@ impl From<char> for UpdateAction {
 }
 
 impl ElementChange {
+    /// Returns the change record as json, with the field type (categoryId) and changed data
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "categoryId": self.category_id,
+            "data": self.data.to_json(),
+        })
+    }
+
     /// Convert Json Value to ChangedData. This is called during deserialization
     /// Most of the parsing logic is devoted to handling ResourceUpdate - activity type 2.
     /// For other activity types (for example, Comment, New Item, etc.)
     /// return the data as ChangedData::Other(Value).
     //noinspection SpellCheckingInspection
     fn from(mut v: Value) -> Result<Self, Error> {
-        use ElementCategoryId::{Categories, Number, Persons, References, Text};
+        use ElementCategoryId::{Categories, Files, Number, Persons, References, Text};
         if let Some(map) = v.as_object_mut() {
             if let Some(change_val) = map.values_mut().take(1).next() {
                 let category_id: ElementCategoryId = match change_val
@@ -2023,6 +2675,7 @@ impl ElementChange {
                     Persons => ChangedData::Persons(serde_json::from_value(change_val)?),
                     References => ChangedData::References(serde_json::from_value(change_val)?),
                     Categories => ChangedData::Categories(serde_json::from_value(change_val)?),
+                    Files => ChangedData::Files(serde_json::from_value(change_val)?),
                     ElementCategoryId::Date => {
                         ChangedData::Date(serde_json::from_value(change_val)?)
                     }
@@ -2036,7 +2689,6 @@ impl ElementChange {
                     | ElementCategoryId::UserCreatedBy
                     | ElementCategoryId::UserUpdatedBy
                     | ElementCategoryId::UserDeprecatedBy
-                    | ElementCategoryId::Files
                     | ElementCategoryId::Hierarchy
                     | ElementCategoryId::SubEntries
                     | ElementCategoryId::Dependencies => {
@@ -2081,3 +2733,64 @@ impl<'de> Deserialize<'de> for ElementChange {
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Color, PredefinedCategory};
+    use crate::list::test::{test_category, test_entry};
+    use serde_json::{json, Value};
+
+    fn category_with_color(color: Value) -> PredefinedCategory {
+        let mut json = test_category(10, "Open", 3);
+        json["colorHex"] = color;
+        serde_json::from_value(json).expect("category deserializes")
+    }
+
+    #[test]
+    fn test_color_lenient() {
+        let cat = category_with_color("#00ff80".into());
+        assert_eq!(cat.color.as_rgb(), Some((0, 255, 128)));
+        assert_eq!(
+            category_with_color("0f8".into()).color.as_rgb(),
+            Some((0, 255, 136))
+        );
+        assert_eq!(category_with_color("".into()).color.as_rgb(), None);
+        assert_eq!(category_with_color("#12345g".into()).color.as_rgb(), None);
+        assert_eq!(category_with_color(Value::Null).color, Color::default());
+        assert_eq!(Color::new("#abc").unwrap().as_rgb(), Some((170, 187, 204)));
+        assert!(Color::new("").is_err());
+        assert!(Color::new("#abcd").is_err());
+    }
+
+    #[test]
+    fn test_get_mirror_values() {
+        let entry = test_entry(json!({
+            "00000000-0000-0000-0000-000000000005_text": "Acme",
+            "00000000-0000-0000-0000-000000000005_references_sort": [{ "id": 2 }],
+            "00000000-0000-0000-0000-000000000005_number": null,
+            "00000000-0000-0000-0000-000000000006_text": "other",
+        }));
+        assert_eq!(
+            entry.get_mirror_values("00000000-0000-0000-0000-000000000005"),
+            vec![
+                ("references_sort", &json!([{ "id": 2 }])),
+                ("text", &json!("Acme")),
+            ]
+        );
+        assert!(entry
+            .get_mirror_values("00000000-0000-0000-0000-000000000007")
+            .is_empty());
+        assert_eq!(
+            entry.get_mirror_value("00000000-0000-0000-0000-000000000006"),
+            Some(&json!("other"))
+        );
+        assert_eq!(
+            entry.get_mirror_value("00000000-0000-0000-0000-000000000005"),
+            Some(&json!([{ "id": 2 }]))
+        );
+        assert_eq!(
+            entry.get_mirror_value("00000000-0000-0000-0000-000000000007"),
+            None
+        );
+    }
+}
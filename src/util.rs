@@ -1,4 +1,6 @@
-use serde::de::{Deserializer, Visitor};
+use crate::Error;
+use serde::de::{DeserializeOwned, Deserializer, Visitor};
+use serde_json::Value;
 use std::fmt;
 
 /// Deserializer for value that can be an int, float, or string
@@ -66,6 +68,29 @@ pub(crate) fn join<T: AsRef<str>>(sep: &str, arr: &[T]) -> String {
     result
 }
 
+/// Deserialize object from json value that is either a single object,
+/// or an array containing the object (first element is used).
+/// Returns error if the array is empty.
+pub(crate) fn from_one_or_array<T: DeserializeOwned>(v: Value) -> Result<T, Error> {
+    let v = match v {
+        Value::Array(arr) => arr
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Other("empty result".to_string()))?,
+        v => v,
+    };
+    serde_json::from_value(v).map_err(json_error::<T>)
+}
+
+/// Constructs a JsonError that includes the name of the type that failed to deserialize
+pub(crate) fn json_error<T>(e: serde_json::Error) -> Error {
+    Error::JsonError(format!(
+        "failed to deserialize {}: {}",
+        std::any::type_name::<T>(),
+        e
+    ))
+}
+
 /// quick test to determine if string is valid uuid
 // avoids dependency on regex lib
 pub(crate) fn is_uuid(s: &str) -> bool {
@@ -97,9 +122,97 @@ pub(crate) fn is_uuid(s: &str) -> bool {
     true
 }
 
+/// Splits a json array into its elements, as response bytes arrive,
+/// so that elements can be deserialized one at a time.
+/// Only the current (incomplete) element is buffered.
+#[derive(Debug, Default)]
+pub(crate) struct JsonArraySplitter {
+    buf: Vec<u8>,
+    // scan position in buf
+    pos: usize,
+    // start of current element in buf
+    elem_start: Option<usize>,
+    started: bool,
+    done: bool,
+    depth: usize,
+    in_string: bool,
+    escape: bool,
+}
+
+impl JsonArraySplitter {
+    /// Appends bytes received
+    pub(crate) fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Returns true after the end of the array has been reached
+    pub(crate) fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the next complete element, or None if more bytes are needed,
+    /// or the end of the array has been reached
+    pub(crate) fn next_element(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        while self.pos < self.buf.len() && !self.done {
+            let c = self.buf[self.pos];
+            let i = self.pos;
+            self.pos += 1;
+            if !self.started {
+                match c {
+                    b'[' => self.started = true,
+                    c if c.is_ascii_whitespace() => {}
+                    _ => return Err(Error::Other("Expected json array".to_string())),
+                }
+                continue;
+            }
+            if self.in_string {
+                if self.escape {
+                    self.escape = false;
+                } else if c == b'\\' {
+                    self.escape = true;
+                } else if c == b'"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match (self.elem_start, c) {
+                // between elements
+                (None, b']') => self.done = true,
+                (None, b',') => {}
+                (None, c) if c.is_ascii_whitespace() => {}
+                (None, c) => {
+                    self.elem_start = Some(i);
+                    self.open_or_close(c);
+                }
+                // end of element
+                (Some(start), b',') | (Some(start), b']') if self.depth == 0 => {
+                    let elem = self.buf[start..i].to_vec();
+                    self.done = c == b']';
+                    self.buf.drain(..self.pos);
+                    self.pos = 0;
+                    self.elem_start = None;
+                    return Ok(Some(elem));
+                }
+                (Some(_), c) => self.open_or_close(c),
+            }
+        }
+        Ok(None)
+    }
+
+    fn open_or_close(&mut self, c: u8) {
+        match c {
+            b'"' => self.in_string = true,
+            b'{' | b'[' => self.depth += 1,
+            b'}' | b']' => self.depth = self.depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{is_uuid, join};
+    use super::{from_one_or_array, is_uuid, join, JsonArraySplitter};
+    use serde_json::json;
 
     #[test]
     fn test_join() -> Result<(), ()> {
@@ -134,4 +247,37 @@ mod test {
             assert_ne!(is_uuid(s), true, "expect bad: {}", s);
         }
     }
+
+    #[test]
+    fn test_one_or_array() {
+        let one: u32 = from_one_or_array(json!(1)).unwrap();
+        assert_eq!(one, 1, "single value");
+        let first: u32 = from_one_or_array(json!([2, 3])).unwrap();
+        assert_eq!(first, 2, "first in array");
+        assert!(from_one_or_array::<u32>(json!([])).is_err(), "empty array");
+    }
+
+    #[test]
+    fn test_json_array_splitter() {
+        let data = br#" [ {"a":"x,]}\"{"}, {"b":[1,2]} ,3 ] "#;
+        // feed in small chunks, to split elements across chunks
+        let mut splitter = JsonArraySplitter::default();
+        let mut elements = Vec::new();
+        for chunk in data.chunks(3) {
+            splitter.push(chunk);
+            while let Some(elem) = splitter.next_element().unwrap() {
+                elements.push(serde_json::from_slice::<serde_json::Value>(&elem).unwrap());
+            }
+        }
+        assert!(splitter.is_done());
+        assert_eq!(
+            elements,
+            vec![json!({"a": "x,]}\"{"}), json!({"b": [1, 2]}), json!(3)]
+        );
+
+        let mut empty = JsonArraySplitter::default();
+        empty.push(b"[]");
+        assert!(empty.next_element().unwrap().is_none());
+        assert!(empty.is_done());
+    }
 }